@@ -0,0 +1,85 @@
+//! Aligning many pairs at once.
+//!
+//! [`Aligner::align_batch`] aligns pairs sequentially with a single aligner.
+//! [`align_parallel`] distributes pairs over threads, each with its own aligner.
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Mutex,
+};
+
+use crate::*;
+
+/// Number of pairs a worker claims at once.
+const CHUNK: usize = 16;
+
+/// Align all `pairs` using `threads` worker threads, and return results in input order.
+///
+/// Each worker constructs its own aligner using `factory`, e.g. `|| aligner.clone()`.
+/// Workers claim small chunks of pairs at a time, so that uneven pair lengths are balanced.
+/// When `threads` is `0`, the available parallelism is used.
+pub fn align_parallel<A, S>(
+    pairs: &[(S, S)],
+    threads: usize,
+    factory: impl Fn() -> A + Sync,
+) -> Vec<(Cost, Option<Cigar>)>
+where
+    A: Aligner,
+    S: AsRef<[Base]> + Sync,
+{
+    let threads = if threads == 0 {
+        std::thread::available_parallelism().map_or(1, |t| t.get())
+    } else {
+        threads
+    };
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(vec![]);
+    std::thread::scope(|scope| {
+        for _ in 0..threads.min(pairs.len().div_ceil(CHUNK)) {
+            scope.spawn(|| {
+                let mut aligner = factory();
+                loop {
+                    let start = next.fetch_add(CHUNK, Ordering::Relaxed);
+                    if start >= pairs.len() {
+                        break;
+                    }
+                    let end = (start + CHUNK).min(pairs.len());
+                    let chunk = aligner.align_batch(
+                        pairs[start..end]
+                            .iter()
+                            .map(|(a, b)| (a.as_ref(), b.as_ref())),
+                    );
+                    results.lock().unwrap().push((start, chunk));
+                }
+            });
+        }
+    });
+    let mut results = results.into_inner().unwrap();
+    results.sort_unstable_by_key(|&(start, _)| start);
+    results.into_iter().flat_map(|(_, chunk)| chunk).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parallel_matches_sequential() {
+        let cm = CostModel::affine(1, 2, 1);
+        let pairs = (0..100)
+            .map(|seed| {
+                let (text, pattern, _) =
+                    generate_pair(&GenerateOptions::uniform(seed as usize, 0.2), seed);
+                (text, pattern)
+            })
+            .collect::<Vec<_>>();
+        let sequential = NW::new(cm, true).align_batch(pairs.iter().map(|(a, b)| (&a[..], &b[..])));
+        assert_eq!(sequential.len(), 100);
+        for threads in [0, 1, 3] {
+            assert_eq!(
+                align_parallel(&pairs, threads, || NW::new(cm, true)),
+                sequential
+            );
+        }
+        assert!(align_parallel::<NW, Sequence>(&[], 4, || NW::new(cm, true)).is_empty());
+    }
+}
@@ -0,0 +1,195 @@
+//! Lightweight benchmarking of [`Aligner`] implementations.
+//!
+//! [`bench()`] runs an aligner over a dataset of sequence pairs, either generated
+//! with [`generate_pair`] or read from a `.seq` file, and measures wall time,
+//! peak memory, and cost correctness. Results are [`BenchResult`]s that can be
+//! written as JSON to track regressions over time.
+//!
+//! Peak memory is counted process-wide, so allocations by other threads are included.
+//! It is only measured when [`CountingAlloc`] is installed as global allocator:
+//! ```ignore
+//! #[global_allocator]
+//! static ALLOC: pa_types::bench::CountingAlloc = pa_types::bench::CountingAlloc;
+//! ```
+//!
+//! Requires the `bench` feature.
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    path::Path as FsPath,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// A global allocator wrapping [`System`] that counts current and peak allocated bytes.
+pub struct CountingAlloc;
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            ACTIVE.store(true, Ordering::Relaxed);
+            let cur = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(cur, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+impl CountingAlloc {
+    /// Whether [`CountingAlloc`] is installed as the global allocator.
+    pub fn is_active() -> bool {
+        ACTIVE.load(Ordering::Relaxed)
+    }
+    /// The number of currently allocated bytes.
+    pub fn current() -> usize {
+        CURRENT.load(Ordering::Relaxed)
+    }
+    /// The maximal number of allocated bytes since the last [`CountingAlloc::reset_peak`].
+    pub fn peak() -> usize {
+        PEAK.load(Ordering::Relaxed)
+    }
+    /// Reset the peak to the currently allocated bytes.
+    pub fn reset_peak() {
+        PEAK.store(CURRENT.load(Ordering::Relaxed), Ordering::Relaxed);
+    }
+}
+
+/// Parse the contents of a `.seq` file, as used by pa-bench.
+///
+/// Each pair consists of a line `>text` followed by a line `<pattern`.
+pub fn parse_seq(data: &str) -> Result<Vec<(Sequence, Sequence)>, String> {
+    let mut pairs = vec![];
+    let mut lines = data.lines().filter(|l| !l.is_empty());
+    while let Some(text) = lines.next() {
+        let text = text
+            .strip_prefix('>')
+            .ok_or_else(|| format!("Expected line starting with '>' but found {text:?}"))?;
+        let pattern = lines.next().ok_or("Missing pattern for last text")?;
+        let pattern = pattern
+            .strip_prefix('<')
+            .ok_or_else(|| format!("Expected line starting with '<' but found {pattern:?}"))?;
+        pairs.push((text.as_bytes().to_vec(), pattern.as_bytes().to_vec()));
+    }
+    Ok(pairs)
+}
+
+/// Read a `.seq` file. See [`parse_seq`].
+pub fn read_seq_file(path: &FsPath) -> std::io::Result<Vec<(Sequence, Sequence)>> {
+    parse_seq(&std::fs::read_to_string(path)?)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// Generate a dataset of `count` pairs, using seeds `0..count`.
+pub fn generate_dataset(opts: &GenerateOptions, count: usize) -> Vec<(Sequence, Sequence)> {
+    (0..count as u64)
+        .map(|seed| {
+            let (text, pattern, _) = generate_pair(opts, seed);
+            (text, pattern)
+        })
+        .collect()
+}
+
+/// The result of running an aligner on a dataset.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BenchResult {
+    /// The number of aligned pairs.
+    pub pairs: usize,
+    /// Total wall time of all alignments, in seconds.
+    pub time_secs: f64,
+    /// Peak memory in bytes above the memory in use at the start,
+    /// or `None` when [`CountingAlloc`] is not installed.
+    pub peak_memory: Option<usize>,
+    /// Sum of returned costs.
+    pub total_cost: i64,
+    /// The number of pairs with an invalid cigar, or cost differing from the cigar cost.
+    pub invalid: usize,
+    /// The number of pairs with non-optimal cost, when checked against [`NW`].
+    pub suboptimal: Option<usize>,
+}
+
+impl BenchResult {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+/// Run `aligner` on all `pairs` and collect statistics.
+///
+/// When `check_optimal` is set, costs are compared to the (slow) reference [`NW`] aligner.
+/// This is done outside the timed region.
+pub fn bench(
+    aligner: &mut impl Aligner,
+    cm: &CostModel,
+    pairs: &[(Sequence, Sequence)],
+    check_optimal: bool,
+) -> BenchResult {
+    let start_mem = CountingAlloc::current();
+    CountingAlloc::reset_peak();
+    let mut time_secs = 0.;
+    let mut total_cost = 0;
+    let mut invalid = 0;
+    let mut costs = vec![];
+    for (text, pattern) in pairs {
+        let start = Instant::now();
+        let (cost, cigar) = aligner.align(text, pattern);
+        time_secs += start.elapsed().as_secs_f64();
+        total_cost += cost as i64;
+        if let Some(cigar) = cigar {
+            if cigar.verify(cm, text, pattern) != Ok(cost) {
+                invalid += 1;
+            }
+        }
+        costs.push(cost);
+    }
+    let peak_memory =
+        CountingAlloc::is_active().then(|| CountingAlloc::peak().saturating_sub(start_mem));
+
+    let suboptimal = check_optimal.then(|| {
+        pairs
+            .iter()
+            .zip(&costs)
+            .filter(|((text, pattern), &cost)| NW::new(*cm, false).align(text, pattern).0 != cost)
+            .count()
+    });
+
+    BenchResult {
+        pairs: pairs.len(),
+        time_secs,
+        peak_memory,
+        total_cost,
+        invalid,
+        suboptimal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let pairs = parse_seq(">ACGT\n<AGT\n\n>A\n<\n").unwrap();
+        assert_eq!(
+            pairs,
+            vec![
+                (b"ACGT".to_vec(), b"AGT".to_vec()),
+                (b"A".to_vec(), b"".to_vec())
+            ]
+        );
+        assert!(parse_seq("<ACGT\n>AGT\n").is_err());
+        assert!(parse_seq(">ACGT\n").is_err());
+    }
+}
@@ -0,0 +1,212 @@
+//! Canonical form of cigars, equivalence checking, and path diffs.
+//!
+//! Different aligners often return different cigars of the same cost.
+//! [`Cigar::canonical`] maps alignments that only differ in the placement of gaps to the same cigar,
+//! and [`equivalent`] compares two cigars this way. [`Cigar::diff`] reports where two paths diverge.
+use crate::*;
+
+impl Cigar {
+    /// Merge adjacent elements with equal operations and drop elements with count `0`.
+    pub fn compact(&mut self) {
+        let ops = std::mem::take(&mut self.ops);
+        for el in ops {
+            if el.cnt != 0 {
+                self.push_elem(el);
+            }
+        }
+    }
+
+    /// Put deletions before insertions in runs of adjacent gaps, when this does not change the cost.
+    ///
+    /// An insertion element directly followed by a deletion element is always swapped, whatever their counts.
+    /// Longer runs are only reordered when gaps have no open cost, since reordering merges gaps.
+    fn order_gaps(&mut self, cm: &CostModel) -> bool {
+        let is_gap = |el: &CigarElem| matches!(el.op, CigarOp::Ins | CigarOp::Del);
+        let mut changed = false;
+        let mut ops = vec![];
+        let mut i = 0;
+        while i < self.ops.len() {
+            let run = self.ops[i..].iter().take_while(|el| is_gap(el)).count();
+            if run == 0 {
+                ops.push(self.ops[i]);
+                i += 1;
+                continue;
+            }
+            let gaps = &self.ops[i..i + run];
+            if run == 2 && gaps[0].op == CigarOp::Ins {
+                ops.extend([gaps[1], gaps[0]]);
+                changed = true;
+            } else if run > 2 && cm.open == 0 {
+                let total = |op| gaps.iter().filter(|el| el.op == op).map(|el| el.cnt).sum();
+                ops.push(CigarElem::new(CigarOp::Del, total(CigarOp::Del)));
+                ops.push(CigarElem::new(CigarOp::Ins, total(CigarOp::Ins)));
+                changed = true;
+            } else {
+                ops.extend_from_slice(gaps);
+            }
+            i += run;
+        }
+        self.ops = ops;
+        changed
+    }
+
+    /// The canonical form of this alignment of `text` and `pattern`.
+    ///
+    /// The result is resolved and compacted, gaps are left-aligned (see [`Cigar::left_align_gaps`]),
+    /// and adjacent deletions come before insertions. The cost under `cm` does not change.
+    /// When gaps have no open cost, gap runs are split and each character is placed individually,
+    /// so alignments that only differ in the position of gaps have the same canonical form.
+    pub fn canonical(&self, text: Seq, pattern: Seq, cm: &CostModel) -> Cigar {
+        let mut c = self.resolve(text, pattern);
+        c.compact();
+        loop {
+            let moved = c.left_align_gaps(text, pattern, cm);
+            let reordered = c.order_gaps(cm);
+            if !moved && !reordered {
+                break;
+            }
+        }
+        c
+    }
+
+    /// The regions where the paths of `self` and `other` differ.
+    ///
+    /// Both paths start at `(0,0)`.
+    pub fn diff(&self, other: &Cigar) -> Vec<Divergence> {
+        let (a, b) = (self.to_path(), other.to_path());
+        let mut divergences = vec![];
+        // Indices of the last common position in both paths.
+        let (mut last_i, mut last_j) = (0, 0);
+        let (mut i, mut j) = (1, 1);
+        while i < a.len() && j < b.len() {
+            // Monotone paths are sorted in lexicographic order.
+            match LexPos(a[i]).cmp(&LexPos(b[j])) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    if i > last_i + 1 || j > last_j + 1 {
+                        divergences.push(Divergence {
+                            start: a[last_i],
+                            end: Some(a[i]),
+                        });
+                    }
+                    (last_i, last_j) = (i, j);
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        if last_i + 1 < a.len() || last_j + 1 < b.len() {
+            divergences.push(Divergence {
+                start: a[last_i],
+                end: None,
+            });
+        }
+        divergences
+    }
+}
+
+/// A region where two paths differ, as returned by [`Cigar::diff`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Divergence {
+    /// The last common position before the paths split.
+    pub start: Pos,
+    /// The first common position after the split, or `None` if the paths do not meet again.
+    pub end: Option<Pos>,
+}
+
+/// Whether `a` and `b` are valid alignments of `text` and `pattern` with the same canonical form.
+///
+/// Equivalent cigars have the same cost under `cm`.
+pub fn equivalent(a: &Cigar, b: &Cigar, text: Seq, pattern: Seq, cm: &CostModel) -> bool {
+    a.verify(cm, text, pattern).is_ok()
+        && b.verify(cm, text, pattern).is_ok()
+        && a.canonical(text, pattern, cm) == b.canonical(text, pattern, cm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compact() {
+        let mut c = Cigar {
+            ops: vec![
+                CigarElem::new(CigarOp::Match, 2),
+                CigarElem::new(CigarOp::Ins, 0),
+                CigarElem::new(CigarOp::Match, 1),
+            ],
+        };
+        c.compact();
+        assert_eq!(c.ops, vec![CigarElem::new(CigarOp::Match, 3)]);
+    }
+
+    #[test]
+    fn canonical() {
+        let cm = CostModel::unit();
+        let (text, pattern) = (b"ACGTT", b"AGGTT");
+        let a = Cigar::from_string("1=1I1D3=");
+        let b = Cigar::from_string("1=1D1I3=");
+        assert_eq!(a.canonical(text, pattern, &cm).to_string(), "1=1D1I3=");
+        assert!(equivalent(&a, &b, text, pattern, &cm));
+        // A different alignment, with a lower cost.
+        assert!(!equivalent(
+            &a,
+            &Cigar::from_string("1=1X3="),
+            text,
+            pattern,
+            &cm
+        ));
+
+        // Gaps in a homopolymer.
+        let (text, pattern) = (b"CAAAT", b"CAAT");
+        let a = Cigar::from_string("1=1D3=");
+        let b = Cigar::from_string("3=1D1=");
+        assert!(equivalent(&a, &b, text, pattern, &cm));
+        assert!(!equivalent(&a, &b, text, b"CAT", &cm));
+
+        // Gaps that are split in one alignment and merged in the other.
+        let a = Cigar::from_string("1=1I1=1I");
+        let b = Cigar::from_string("2=2I");
+        assert!(equivalent(&a, &b, b"AC", b"ACCA", &cm));
+    }
+
+    #[test]
+    fn equivalent_to_right_aligned() {
+        for cm in [CostModel::unit(), CostModel::linear(2, 1)] {
+            for error_model in [ErrorModel::Uniform, ErrorModel::Repeat { unit_len: 2 }] {
+                for seed in 0..200 {
+                    let opts = GenerateOptions {
+                        error_model,
+                        ..GenerateOptions::uniform(30, 0.3)
+                    };
+                    let (text, pattern, c) = generate_pair(&opts, seed);
+                    let mut r = c.clone();
+                    r.right_align_gaps(&text, &pattern, &cm);
+                    assert!(equivalent(&c, &r, &text, &pattern, &cm));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn diff() {
+        let a = Cigar::from_string("1=1D3=1X");
+        let b = Cigar::from_string("3=1D1=1X");
+        assert_eq!(a.diff(&a), vec![]);
+        assert_eq!(
+            a.diff(&b),
+            vec![Divergence {
+                start: Pos(1, 1),
+                end: Some(Pos(4, 3))
+            }]
+        );
+        assert_eq!(
+            a.diff(&Cigar::from_string("1=1I")),
+            vec![Divergence {
+                start: Pos(1, 1),
+                end: None
+            }]
+        );
+    }
+}
@@ -0,0 +1,207 @@
+//! Building a global alignment from partial alignments.
+//!
+//! Seed-and-extend and divide-and-conquer aligners find alignments of pieces of the input.
+//! [`Cigar::concat`] joins cigars, [`Cigar::join`] additionally checks that pieces given by their
+//! start [`Pos`] are contiguous, and [`chain()`] fills the gaps between pieces using an [`Aligner`].
+use crate::*;
+
+/// Reasons a list of pieces can not be joined into a global alignment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainError {
+    /// Piece `index` starts at `found` instead of at `expected`, the end of the previous piece.
+    NotContiguous {
+        index: usize,
+        expected: Pos,
+        found: Pos,
+    },
+    /// Piece `index` starts at `start`, which is not after `prev_end`, the end of the previous piece.
+    Overlap {
+        index: usize,
+        prev_end: Pos,
+        start: Pos,
+    },
+    /// Piece `index` ends at `end`, outside the `(text, pattern)` grid.
+    OutOfBounds { index: usize, end: Pos },
+    /// The aligner used to fill a gap did not return a cigar.
+    NoTraceback,
+}
+
+impl std::fmt::Display for ChainError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChainError::NotContiguous {
+                index,
+                expected,
+                found,
+            } => write!(f, "Piece {index} starts at {found} instead of {expected}"),
+            ChainError::Overlap {
+                index,
+                prev_end,
+                start,
+            } => write!(
+                f,
+                "Piece {index} starts at {start}, before the previous piece ends at {prev_end}"
+            ),
+            ChainError::OutOfBounds { index, end } => {
+                write!(f, "Piece {index} ends out of bounds at {end}")
+            }
+            ChainError::NoTraceback => write!(f, "Aligner did not return a cigar"),
+        }
+    }
+}
+
+impl std::error::Error for ChainError {}
+
+impl Cigar {
+    /// The total `(text, pattern)` length covered by the cigar.
+    pub fn delta(&self) -> Pos {
+        self.ops
+            .iter()
+            .fold(Pos(0, 0), |pos, el| pos + el.op.delta() * el.cnt)
+    }
+
+    /// Append `other`, merging the boundary elements when they have the same operation.
+    pub fn append(&mut self, other: &Cigar) {
+        for &el in &other.ops {
+            self.push_elem(el);
+        }
+    }
+
+    /// Concatenate cigars, merging boundary elements.
+    pub fn concat<'c>(cigars: impl IntoIterator<Item = &'c Cigar>) -> Cigar {
+        let mut c = Cigar::default();
+        for other in cigars {
+            c.append(other);
+        }
+        c
+    }
+
+    /// Concatenate pieces `(start, cigar)`, checking that each piece starts where the previous one ends.
+    ///
+    /// The first piece may start anywhere.
+    pub fn join(pieces: &[(Pos, Cigar)]) -> Result<Cigar, ChainError> {
+        validate_pieces(pieces)?;
+        Ok(Cigar::concat(pieces.iter().map(|(_, c)| c)))
+    }
+}
+
+/// Check that each piece `(start, cigar)` starts where the previous one ends.
+pub fn validate_pieces(pieces: &[(Pos, Cigar)]) -> Result<(), ChainError> {
+    for (index, w) in pieces.windows(2).enumerate() {
+        let expected = w[0].0 + w[0].1.delta();
+        if w[1].0 != expected {
+            return Err(ChainError::NotContiguous {
+                index: index + 1,
+                expected,
+                found: w[1].0,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Build a global alignment of `text` and `pattern` from pieces `(start, cigar)`, sorted by start.
+///
+/// The gaps before the first piece, between consecutive pieces, and after the last piece
+/// are aligned with `aligner`, which must return a cigar.
+pub fn chain(
+    text: Seq,
+    pattern: Seq,
+    pieces: &[(Pos, Cigar)],
+    aligner: &mut (impl Aligner + ?Sized),
+) -> Result<Cigar, ChainError> {
+    let target = Pos::target(text, pattern);
+    let mut fill = |c: &mut Cigar, from: Pos, to: Pos| -> Result<(), ChainError> {
+        if from == to {
+            return Ok(());
+        }
+        let (_, gap) = aligner.align(
+            &text[from.0 as usize..to.0 as usize],
+            &pattern[from.1 as usize..to.1 as usize],
+        );
+        c.append(&gap.ok_or(ChainError::NoTraceback)?);
+        Ok(())
+    };
+
+    let mut c = Cigar::default();
+    let mut pos = Pos(0, 0);
+    for (index, (start, piece)) in pieces.iter().enumerate() {
+        if start.0 < pos.0 || start.1 < pos.1 {
+            return Err(ChainError::Overlap {
+                index,
+                prev_end: pos,
+                start: *start,
+            });
+        }
+        let end = *start + piece.delta();
+        if end.0 > target.0 || end.1 > target.1 {
+            return Err(ChainError::OutOfBounds { index, end });
+        }
+        fill(&mut c, pos, *start)?;
+        c.append(piece);
+        pos = end;
+    }
+    fill(&mut c, pos, target)?;
+    Ok(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concat() {
+        let a = Cigar::from_string("2=1I");
+        let b = Cigar::from_string("1I3=");
+        let c = Cigar::concat([&a, &b]);
+        assert_eq!(c.to_string(), "2=2I3=");
+        assert_eq!(c.ops.len(), 3);
+        assert_eq!(c.delta(), Pos(5, 7));
+    }
+
+    #[test]
+    fn join() {
+        let pieces = [
+            (Pos(1, 1), Cigar::from_string("2=1D")),
+            (Pos(4, 3), Cigar::from_string("1X")),
+        ];
+        assert_eq!(Cigar::join(&pieces).unwrap().to_string(), "2=1D1X");
+        assert_eq!(
+            Cigar::join(&[pieces[0].clone(), (Pos(4, 4), Cigar::from_string("1="))]),
+            Err(ChainError::NotContiguous {
+                index: 1,
+                expected: Pos(4, 3),
+                found: Pos(4, 4)
+            })
+        );
+    }
+
+    #[test]
+    fn chain_fills_gaps() {
+        let text = b"ACGTTGCA";
+        let pattern = b"AGTTGGCA";
+        let pieces = [
+            (Pos(2, 1), Cigar::from_string("3=")),
+            (Pos(6, 6), Cigar::from_string("1=")),
+        ];
+        let mut nw = NW::new(CostModel::unit(), true);
+        let c = chain(text, pattern, &pieces, &mut nw).unwrap();
+        assert_eq!(c.verify(&CostModel::unit(), text, pattern), Ok(2));
+        assert_eq!(c.to_string(), "1=1D3=1I3=");
+
+        let overlapping = [pieces[1].clone(), pieces[0].clone()];
+        assert_eq!(
+            chain(text, pattern, &overlapping, &mut nw),
+            Err(ChainError::Overlap {
+                index: 1,
+                prev_end: Pos(7, 7),
+                start: Pos(2, 1)
+            })
+        );
+        let mut no_trace = NW::new(CostModel::unit(), false);
+        assert_eq!(
+            chain(text, pattern, &pieces, &mut no_trace),
+            Err(ChainError::NoTraceback)
+        );
+    }
+}
@@ -5,7 +5,8 @@ Uses SAM cigar conventions. See <https://timd.one/blog/genomics/cigar.php> (tabl
 
 In particular, an "insertion" is a character in the pattern/query (2nd `Pos` coordinate) that is not in the text/reference (first `Pos` coordinate), and opposite for deletions.
 
-We never output `M` characters, but do parse these into `=` and `X`, which need the corresponding text/pattern string to be resolved.
+`M` characters are represented by [`CigarOp::AlnMatch`]. They can be resolved into `=` and `X` using the corresponding text/pattern strings,
+via [`Cigar::resolve`], [`Cigar::parse`], or [`Cigar::resolve_matches`].
 
 Undetermined bases (`N`), clipping (`S`, `H`), and padding (`P`) are not supported.
 
@@ -29,7 +30,6 @@ Undetermined bases (`N`), clipping (`S`, `H`), and padding (`P`) are not support
 
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use std::fmt::Write;
 
 use crate::*;
 
@@ -44,6 +44,8 @@ pub enum CigarOp {
     Del,
     /// `I`
     Ins,
+    /// `M`: a match or substitution that is not resolved yet.
+    AlnMatch,
 }
 
 /// A cigar string character with the corresponding characters from text and pattern.
@@ -83,13 +85,14 @@ pub struct Cigar {
 }
 
 impl CigarOp {
-    /// Convert to one of `=XID`.
+    /// Convert to one of `=XIDM`.
     pub fn to_char(&self) -> char {
         match self {
             CigarOp::Match => '=',
             CigarOp::Sub => 'X',
             CigarOp::Ins => 'I',
             CigarOp::Del => 'D',
+            CigarOp::AlnMatch => 'M',
         }
     }
 
@@ -97,7 +100,7 @@ impl CigarOp {
     #[inline(always)]
     pub fn delta(&self) -> Pos {
         match self {
-            CigarOp::Match | CigarOp::Sub => Pos(1, 1),
+            CigarOp::Match | CigarOp::Sub | CigarOp::AlnMatch => Pos(1, 1),
             CigarOp::Del => Pos(1, 0), // deletion: consumes ref/text only
             CigarOp::Ins => Pos(0, 1), // insertion: consumes pattern/query only
         }
@@ -108,11 +111,19 @@ impl CigarOp {
     /// Ignores [`CigarOp::Sub`]: (1,1) is always [`CigarOp::Match`].
     #[inline(always)]
     pub fn from_delta(delta: Pos) -> Self {
+        Self::try_from_delta(delta).unwrap_or_else(|| panic!("Invalid delta: {:?}", delta))
+    }
+
+    /// Converts path delta `(text_pos, pattern_pos)` to `CigarOp`, or `None` for non-unit steps.
+    ///
+    /// Ignores [`CigarOp::Sub`]: (1,1) is always [`CigarOp::Match`].
+    #[inline(always)]
+    pub fn try_from_delta(delta: Pos) -> Option<Self> {
         match delta {
-            Pos(0, 1) => CigarOp::Ins,
-            Pos(1, 0) => CigarOp::Del,
-            Pos(1, 1) => CigarOp::Match,
-            _ => panic!("Invalid delta: {:?}", delta),
+            Pos(0, 1) => Some(CigarOp::Ins),
+            Pos(1, 0) => Some(CigarOp::Del),
+            Pos(1, 1) => Some(CigarOp::Match),
+            _ => None,
         }
     }
 }
@@ -128,10 +139,11 @@ impl std::ops::Mul<I> for Pos {
 impl From<u8> for CigarOp {
     /// Convert from `=MXID` to `CigarOp`.
     ///
-    /// `M` is interpreted as `=` ([`CigarOp::Match`]) and should be resolved into `=` or `X` ([`CigarOp::Sub`]) via `resolve_matches`.
+    /// `M` is converted to [`CigarOp::AlnMatch`] and should be resolved into `=` ([`CigarOp::Match`]) or `X` ([`CigarOp::Sub`]) via [`Cigar::resolve`].
     fn from(op: u8) -> Self {
         match op {
-            b'=' | b'M' => CigarOp::Match,
+            b'=' => CigarOp::Match,
+            b'M' => CigarOp::AlnMatch,
             b'X' => CigarOp::Sub,
             b'I' => CigarOp::Ins,
             b'D' => CigarOp::Del,
@@ -140,14 +152,82 @@ impl From<u8> for CigarOp {
     }
 }
 
-impl ToString for Cigar {
-    /// Format the cigar to a `String`.
-    fn to_string(&self) -> String {
-        let mut s = String::new();
+impl std::fmt::Display for Cigar {
+    /// Format the cigar, e.g. `3=1X2I`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for elem in &self.ops {
-            write!(&mut s, "{}{}", elem.cnt, elem.op.to_char()).unwrap();
+            write!(f, "{}{}", elem.cnt, elem.op.to_char())?;
+        }
+        Ok(())
+    }
+}
+
+/// Reasons a string is not a valid [`Cigar`], see the [`FromStr`](std::str::FromStr) implementation of [`Cigar`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseCigarError {
+    /// A character that is not one of `=XIDM`.
+    UnknownOp(char),
+    /// A count at the end of the string, without an operation.
+    MissingOp(String),
+    /// A count that does not fit in [`I`].
+    InvalidCount(String),
+    /// The cigar spans more than [`I::MAX`] characters of the text or pattern.
+    TooLong,
+}
+
+impl std::fmt::Display for ParseCigarError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseCigarError::UnknownOp(c) => write!(f, "Unknown operation {c:?}"),
+            ParseCigarError::MissingOp(cnt) => {
+                write!(f, "Count {cnt:?} is not followed by an operation")
+            }
+            ParseCigarError::InvalidCount(cnt) => write!(f, "Invalid count {cnt:?}"),
+            ParseCigarError::TooLong => {
+                write!(f, "Cigar spans more than {} characters", I::MAX)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseCigarError {}
+
+impl std::str::FromStr for Cigar {
+    type Err = ParseCigarError;
+
+    /// Parse a cigar string with optional counts, e.g. `3=1X2I` or `4M2I`.
+    ///
+    /// `M` chars are *not* resolved and kept as [`CigarOp::AlnMatch`]. See [`Cigar::resolve`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut c = Cigar::default();
+        // The end position bounds all merged counts, so checking it prevents overflows.
+        let mut end = Pos(0, 0);
+        for slice in s.as_bytes().split_inclusive(|b| !b.is_ascii_digit()) {
+            let (&op, cnt_bytes) = slice.split_last().unwrap();
+            // Only ASCII digits remain.
+            let cnt_str = |bytes| std::str::from_utf8(bytes).unwrap().to_string();
+            if op.is_ascii_digit() {
+                return Err(ParseCigarError::MissingOp(cnt_str(slice)));
+            }
+            if !b"=XIDM".contains(&op) {
+                return Err(ParseCigarError::UnknownOp(op as char));
+            }
+            let cnt = if cnt_bytes.is_empty() {
+                1
+            } else {
+                cnt_str(cnt_bytes)
+                    .parse()
+                    .map_err(|_| ParseCigarError::InvalidCount(cnt_str(cnt_bytes)))?
+            };
+            let op = CigarOp::from(op);
+            let delta = op.delta() * cnt;
+            end = Pos(
+                end.0.checked_add(delta.0).ok_or(ParseCigarError::TooLong)?,
+                end.1.checked_add(delta.1).ok_or(ParseCigarError::TooLong)?,
+            );
+            c.push_elem(CigarElem { op, cnt });
         }
-        s
+        Ok(c)
     }
 }
 
@@ -219,6 +299,14 @@ impl Cigar {
                         // Note insertion consumes pattern hence pattern slice
                         CigarOpChars::Ins(pattern[pos.1 as usize])
                     }
+                    CigarOp::AlnMatch => {
+                        let (t, p) = (text[pos.0 as usize], pattern[pos.1 as usize]);
+                        if t == p {
+                            CigarOpChars::Match(t)
+                        } else {
+                            CigarOpChars::Sub(t, p)
+                        }
+                    }
                 };
                 out.push(c);
                 pos += el.op.delta();
@@ -240,43 +328,51 @@ impl Cigar {
         path
     }
 
-    /// Get the `Path` and the alignment `Cost` to each position.
-    pub fn to_path_with_costs(&self, cm: CostModel) -> Vec<(Pos, Cost)> {
+    /// The incremental cost of each single operation, with [`CigarOp::AlnMatch`] resolved.
+    ///
+    /// The `k`th character of a gap costs `gap(k) - gap(k-1)`, so the open cost is charged
+    /// to the first character. Diagonal operations cost `cm.sub_cost(t, p)`.
+    pub fn op_costs(&self, cm: &impl Costs, text: Seq, pattern: Seq) -> Vec<(CigarOp, Cost)> {
         let mut pos = Pos(0, 0);
-        let mut cost = 0;
-        let mut path = vec![(pos, cost)];
-
+        let mut costs = vec![];
         for el in &self.ops {
-            match el.op {
-                CigarOp::Match => {
-                    for _ in 0..el.cnt {
-                        pos += el.op.delta();
-                        path.push((pos, cost));
-                    }
-                }
-                CigarOp::Sub => {
-                    for _ in 0..el.cnt {
-                        pos += el.op.delta();
-                        cost += cm.sub;
-                        path.push((pos, cost));
+            for k in 1..=el.cnt {
+                let cost = match el.op {
+                    CigarOp::Match | CigarOp::Sub | CigarOp::AlnMatch => {
+                        cm.sub_cost(text[pos.0 as usize], pattern[pos.1 as usize])
                     }
-                }
-                CigarOp::Ins => {
-                    for len in 1..=(el.cnt as Cost) {
-                        pos += el.op.delta();
-                        path.push((pos, cost + cm.ins(len)));
-                    }
-                    cost += cm.ins(el.cnt);
-                }
-                CigarOp::Del => {
-                    for len in 1..=(el.cnt as Cost) {
-                        pos += el.op.delta();
-                        path.push((pos, cost + cm.del(len)));
+                    CigarOp::Ins if k == 1 => cm.ins(1),
+                    CigarOp::Ins => cm.ins(k) - cm.ins(k - 1),
+                    CigarOp::Del if k == 1 => cm.del(1),
+                    CigarOp::Del => cm.del(k) - cm.del(k - 1),
+                };
+                let op = match el.op {
+                    CigarOp::AlnMatch if text[pos.0 as usize] == pattern[pos.1 as usize] => {
+                        CigarOp::Match
                     }
-                    cost += cm.del(el.cnt);
-                }
+                    CigarOp::AlnMatch => CigarOp::Sub,
+                    op => op,
+                };
+                costs.push((op, cost));
+                pos += el.op.delta();
             }
         }
+        costs
+    }
+
+    /// Get the `Path` and the alignment `Cost` to each position.
+    ///
+    /// Costs inside a gap include the full cost of the gap up to that position.
+    /// See [`Cigar::op_costs`] for the cost of each operation.
+    pub fn to_path_with_costs(&self, cm: &impl Costs, text: Seq, pattern: Seq) -> Vec<(Pos, Cost)> {
+        let mut pos = Pos(0, 0);
+        let mut cost = 0;
+        let mut path = vec![(pos, cost)];
+        for (op, c) in self.op_costs(cm, text, pattern) {
+            pos += op.delta();
+            cost += c;
+            path.push((pos, cost));
+        }
         path
     }
 
@@ -317,35 +413,41 @@ impl Cigar {
     }
 
     /// Check that the cigar is valid between `text` and `pattern` and return the cost.
-    pub fn verify(&self, cm: &CostModel, text: Seq, pattern: Seq) -> Result<Cost, &str> {
+    pub fn verify(&self, cm: &impl Costs, text: Seq, pattern: Seq) -> Result<Cost, &str> {
         let mut pos = Pos(0, 0);
         let mut cost: Cost = 0;
+        let chars = |pos: Pos| text.get(pos.0 as usize).zip(pattern.get(pos.1 as usize));
 
         for &CigarElem { op, cnt } in &self.ops {
             match op {
-                CigarOp::Match => {
+                CigarOp::Match | CigarOp::Sub | CigarOp::AlnMatch => {
                     for _ in 0..cnt {
-                        if text.get(pos.0 as usize) != pattern.get(pos.1 as usize) {
+                        let Some((&t, &p)) = chars(pos) else {
+                            return Err("Wrong alignment length.");
+                        };
+                        if op == CigarOp::Match && t != p {
                             return Err("Expected match but found substitution.");
                         }
-                        pos += op.delta();
-                    }
-                }
-                CigarOp::Sub => {
-                    for _ in 0..cnt {
-                        if text.get(pos.0 as usize) == pattern.get(pos.1 as usize) {
+                        if op == CigarOp::Sub && t == p {
                             return Err("Expected substitution but found match.");
                         }
+                        if !cm.supports_char(t) || !cm.supports_char(p) {
+                            return Err("Character not supported by the cost model.");
+                        }
+                        cost += cm.sub_cost(t, p);
                         pos += op.delta();
-                        cost += cm.sub;
                     }
                 }
                 CigarOp::Ins => {
-                    cost += cm.open + cnt as Cost * cm.extend;
+                    if cnt > 0 {
+                        cost += cm.ins(cnt);
+                    }
                     pos += op.delta() * cnt;
                 }
                 CigarOp::Del => {
-                    cost += cm.open + cnt as Cost * cm.extend;
+                    if cnt > 0 {
+                        cost += cm.del(cnt);
+                    }
                     pos += op.delta() * cnt;
                 }
             }
@@ -357,13 +459,13 @@ impl Cigar {
         Ok(cost)
     }
 
-    /// Splits all 'M'/[`CigarOp::Match`] into matches (`=`) and substitutions (`X`), and joins consecutive equal elements.
+    /// Splits all 'M'/[`CigarOp::AlnMatch`] and [`CigarOp::Match`] into matches (`=`) and substitutions (`X`), and joins consecutive equal elements.
     pub fn resolve_matches(ops: impl Iterator<Item = CigarElem>, text: Seq, pattern: Seq) -> Self {
         let mut pos = Pos(0, 0);
         let mut c = Cigar { ops: vec![] };
         for CigarElem { op, cnt } in ops {
             match op {
-                CigarOp::Match => {
+                CigarOp::Match | CigarOp::AlnMatch => {
                     for _ in 0..cnt {
                         c.push(if text[pos.0 as usize] == pattern[pos.1 as usize] {
                             CigarOp::Match
@@ -396,8 +498,8 @@ impl Cigar {
         )
     }
 
-    /// A simpler parsing function that only parses strings of characters `MXID`, without preceding counts.
-    /// Consecutive characters are grouped. `M` chars are *not* resolved and assumed to mean `=`.
+    /// A simpler parsing function that only parses strings of characters `M=XID`, without preceding counts.
+    /// Consecutive characters are grouped. `M` chars are *not* resolved and kept as [`CigarOp::AlnMatch`].
     pub fn parse_without_resolving(s: &str) -> Self {
         let mut c = Cigar { ops: vec![] };
         for &op in s.as_bytes() {
@@ -406,21 +508,15 @@ impl Cigar {
         c
     }
 
-    /// Parse a Cigar string with optional counts
+    /// Parse a Cigar string with optional counts.
+    ///
+    /// `M` chars are *not* resolved and kept as [`CigarOp::AlnMatch`]. See [`Cigar::resolve`].
+    ///
+    /// # Panics
+    /// When the string is not a valid cigar. Use [`str::parse`] to handle errors.
     pub fn from_string(s: &str) -> Self {
-        let mut c = Cigar { ops: vec![] };
-        for slice in s.as_bytes().split_inclusive(|b| !b.is_ascii_digit()) {
-            let (&op, cnt_bytes) = slice.split_last().expect("Cigar string cannot be empty");
-            let cnt = if cnt_bytes.is_empty() {
-                1
-            } else {
-                unsafe { std::str::from_utf8_unchecked(cnt_bytes) }
-                    .parse()
-                    .expect("Invalid Cigar count")
-            };
-            c.push_elem(CigarElem { op: op.into(), cnt });
-        }
-        c
+        s.parse()
+            .unwrap_or_else(|e| panic!("Invalid cigar {s:?}: {e}"))
     }
 
     /// A more generic (and slower) parsing function that also allows optional counts, e.g. `5M2X3M`.
@@ -428,7 +524,7 @@ impl Cigar {
     pub fn parse(s: &str, text: Seq, pattern: Seq) -> Self {
         Self::resolve_matches(
             s.as_bytes()
-                .split_inclusive(|pattern| pattern.is_ascii_alphabetic())
+                .split_inclusive(|b| !b.is_ascii_digit())
                 .map(|pattern_slice| {
                     let (&op, cnt) = pattern_slice.split_last().unwrap();
                     let cnt = if cnt.is_empty() {
@@ -445,6 +541,56 @@ impl Cigar {
         )
     }
 
+    /// Create a Cigar from a path without the sequences.
+    ///
+    /// Diagonal steps become unresolved [`CigarOp::AlnMatch`] (`M`) elements,
+    /// which can later be resolved using [`Cigar::resolve`].
+    ///
+    /// # Panics
+    /// Like [`Cigar::from_path`], when the path does not start at `(0,0)` or has a non-unit step.
+    /// Use [`Cigar::try_from_path`] to validate paths.
+    pub fn from_path_unresolved(path: &Path) -> Cigar {
+        if path[0] != Pos(0, 0) {
+            panic!("Path must start at (0,0)!");
+        }
+        Self::from_ops(path.iter().tuple_windows().map(|(&a, &b)| {
+            match CigarOp::from_delta(b - a) {
+                CigarOp::Match => CigarOp::AlnMatch,
+                op => op,
+            }
+        }))
+    }
+
+    /// Whether the cigar contains no unresolved [`CigarOp::AlnMatch`] elements.
+    pub fn is_resolved(&self) -> bool {
+        self.ops.iter().all(|e| e.op != CigarOp::AlnMatch)
+    }
+
+    /// Resolve all [`CigarOp::AlnMatch`] elements into `=` and `X` using `text` and `pattern`.
+    ///
+    /// Explicit `=` and `X` elements are kept as written, even when they do not agree with the sequences.
+    /// Use [`Cigar::resolve_matches`] to also re-resolve those.
+    pub fn resolve(&self, text: Seq, pattern: Seq) -> Cigar {
+        let mut pos = Pos(0, 0);
+        let mut c = Cigar::default();
+        for &el in &self.ops {
+            if el.op != CigarOp::AlnMatch {
+                c.push_elem(el);
+                pos += el.op.delta() * el.cnt;
+                continue;
+            }
+            for _ in 0..el.cnt {
+                c.push(if text[pos.0 as usize] == pattern[pos.1 as usize] {
+                    CigarOp::Match
+                } else {
+                    CigarOp::Sub
+                });
+                pos += el.op.delta();
+            }
+        }
+        c
+    }
+
     /// Clear the internal vector.
     pub fn clear(&mut self) {
         self.ops.clear();
@@ -531,6 +677,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn from_str_errors() {
+        assert_eq!("3=2I".parse(), Ok(Cigar::from_string("3=2I")));
+        assert_eq!("3Q".parse::<Cigar>(), Err(ParseCigarError::UnknownOp('Q')));
+        assert_eq!(
+            "3=2".parse::<Cigar>(),
+            Err(ParseCigarError::MissingOp("2".into()))
+        );
+        assert_eq!(
+            "9999999999=".parse::<Cigar>(),
+            Err(ParseCigarError::InvalidCount("9999999999".into()))
+        );
+        // Merged counts and the end position overflow.
+        assert_eq!(
+            "2147483647I1I".parse::<Cigar>(),
+            Err(ParseCigarError::TooLong)
+        );
+        assert_eq!(
+            "2000000000M2000000000D".parse::<Cigar>(),
+            Err(ParseCigarError::TooLong)
+        );
+    }
+
     #[test]
     fn from_string_no_counts() {
         let c = Cigar::from_string("=XIDDD");
@@ -545,6 +714,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_with_counts() {
+        let c = Cigar::parse("2=1X1I", b"ACG", b"ACTG");
+        assert_eq!(c.to_string(), "2=1X1I");
+        let c = Cigar::parse("3M1I", b"ACG", b"ACTG");
+        assert_eq!(c.to_string(), "2=1X1I");
+    }
+
+    #[test]
+    fn unresolved_matches() {
+        let c = Cigar::from_string("3M1I");
+        assert_eq!(c.ops[0], CigarElem::new(CigarOp::AlnMatch, 3));
+        assert_eq!(c.to_string(), "3M1I");
+        assert!(!c.is_resolved());
+        assert_eq!(c.verify(&CostModel::unit(), b"ACG", b"ACTG"), Ok(2));
+
+        let path = c.to_path();
+        let u = Cigar::from_path_unresolved(&path);
+        assert_eq!(u, c);
+        let r = u.resolve(b"ACG", b"ACTG");
+        assert!(r.is_resolved());
+        assert_eq!(r, Cigar::from_path(b"ACG", b"ACTG", &path));
+        assert_eq!(r.to_string(), "2=1X1I");
+
+        // Explicit matches are not re-resolved.
+        let c = Cigar::from_string("1M2=");
+        assert_eq!(c.resolve(b"ACG", b"GCT").to_string(), "1X2=");
+    }
+
+    #[test]
+    #[should_panic(expected = "Path must start at (0,0)!")]
+    fn from_path_unresolved_start() {
+        Cigar::from_path_unresolved(&vec![Pos(1, 1), Pos(2, 2)]);
+    }
+
     #[test]
     #[rustfmt::skip]
     fn push_to_path() {
@@ -0,0 +1,129 @@
+//! Composing alignments that share a sequence, e.g. to lift read→contig alignments to read→reference.
+//!
+//! When `a` aligns text `x` to pattern `y`, and `b` aligns text `y` to pattern `z`,
+//! `a.compose(&b)` is the induced alignment of text `x` to pattern `z`.
+//! Use [`Cigar::invert`] to swap the roles of text and pattern of either input.
+use crate::*;
+
+impl CigarOp {
+    /// The operation with text and pattern swapped: insertions become deletions and vice versa.
+    pub fn invert(&self) -> CigarOp {
+        match self {
+            CigarOp::Ins => CigarOp::Del,
+            CigarOp::Del => CigarOp::Ins,
+            op => *op,
+        }
+    }
+}
+
+impl Cigar {
+    /// The alignment with text and pattern swapped: insertions become deletions and vice versa.
+    pub fn invert(&self) -> Cigar {
+        Cigar {
+            ops: self
+                .ops
+                .iter()
+                .map(|el| CigarElem::new(el.op.invert(), el.cnt))
+                .collect(),
+        }
+    }
+
+    /// Compose `self`, aligning `x` to `y`, with `other`, aligning `y` to `z`, into an alignment of `x` to `z`.
+    ///
+    /// Characters of `y` that are deleted in both inputs are dropped.
+    /// Matches composed with substitutions are substitutions, but two substitutions
+    /// may or may not give a match, so they become [`CigarOp::AlnMatch`].
+    /// Use [`Cigar::resolve`] with `x` and `z` to resolve those.
+    ///
+    /// Panics when the pattern length of `self` differs from the text length of `other`.
+    pub fn compose(&self, other: &Cigar) -> Cigar {
+        assert_eq!(
+            self.delta().1,
+            other.delta().0,
+            "The pattern of the first cigar must be the text of the second cigar."
+        );
+        let expand = |c: &Cigar| {
+            c.ops
+                .iter()
+                .flat_map(|e| std::iter::repeat_n(e.op, e.cnt as usize))
+                .collect::<Vec<_>>()
+        };
+        let (a, b) = (expand(self), expand(other));
+
+        let mut c = Cigar::default();
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() || j < b.len() {
+            // Operations not involving the middle sequence are copied directly.
+            if i < a.len() && a[i] == CigarOp::Del {
+                c.push(CigarOp::Del);
+                i += 1;
+                continue;
+            }
+            if j < b.len() && b[j] == CigarOp::Ins {
+                c.push(CigarOp::Ins);
+                j += 1;
+                continue;
+            }
+            // Both consume the next character of the middle sequence.
+            let op = match (a[i], b[j]) {
+                (CigarOp::Ins, CigarOp::Del) => None,
+                (CigarOp::Ins, _) => Some(CigarOp::Ins),
+                (_, CigarOp::Del) => Some(CigarOp::Del),
+                (CigarOp::Match, CigarOp::Match) => Some(CigarOp::Match),
+                (CigarOp::Match, CigarOp::Sub) | (CigarOp::Sub, CigarOp::Match) => {
+                    Some(CigarOp::Sub)
+                }
+                _ => Some(CigarOp::AlnMatch),
+            };
+            if let Some(op) = op {
+                c.push(op);
+            }
+            i += 1;
+            j += 1;
+        }
+        c
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invert() {
+        let c = Cigar::from_string("2=1I1X3D");
+        assert_eq!(c.invert().to_string(), "2=1D1X3I");
+        assert_eq!(c.invert().invert(), c);
+        let (text, pattern) = (b"ACGTA", b"ACGAAAA");
+        let c = Cigar::from_string("3=1X1=2I");
+        let cm = CostModel::affine(1, 2, 1);
+        assert_eq!(
+            c.invert().verify(&cm, pattern, text),
+            c.verify(&cm, text, pattern)
+        );
+    }
+
+    #[test]
+    fn compose() {
+        let x = b"ACGTTA";
+        let y = b"AGTCTA";
+        let z = b"ATTCTAC";
+        let xy = Cigar::from_string("1=1D2=1I1=1=");
+        let yz = Cigar::from_string("1=1X1=3=1I");
+        let cm = CostModel::unit();
+        assert_eq!(xy.verify(&cm, x, y), Ok(2));
+        assert_eq!(yz.verify(&cm, y, z), Ok(2));
+        let xz = xy.compose(&yz);
+        assert_eq!(xz.to_string(), "1=1D1X1=1I2=1I");
+        assert_eq!(xz.verify(&cm, x, z), Ok(4));
+
+        // Two substitutions compose to an unresolved match.
+        let c = Cigar::from_string("1X").compose(&Cigar::from_string("1X"));
+        assert_eq!(c.ops, vec![CigarElem::new(CigarOp::AlnMatch, 1)]);
+        assert_eq!(c.resolve(b"A", b"A").to_string(), "1=");
+
+        // A character deleted from both sides disappears.
+        let c = Cigar::from_string("1=1I").compose(&Cigar::from_string("1=1D"));
+        assert_eq!(c.to_string(), "1=");
+    }
+}
@@ -1,7 +1,12 @@
 //! `i32` [`Cost`] and [`Score`], affine [`CostModel`] and [`ScoreModel`].
+//!
+//! The [`Costs`] trait abstracts over cost models, and is implemented by
+//! [`CostModel`], [`DualAffine`], and the substitution matrix based [`MatrixCost`].
 use serde::{Deserialize, Serialize};
 
-use crate::I;
+use std::ops::RangeInclusive;
+
+use crate::{Base, Pos, I};
 
 /// The non-negative cost of an alignment.
 pub type Cost = i32;
@@ -73,6 +78,350 @@ impl CostModel {
     pub fn del(&self, len: I) -> Cost {
         self.open + len * self.extend
     }
+
+    /// A lower bound on the cost of a path from `from` to `to`: the cost of the gap needed to change diagonal.
+    pub fn gap_lower_bound(&self, from: Pos, to: Pos) -> Cost {
+        self.min_gap(from.diag_distance(to))
+    }
+
+    /// The maximal number of edits (substituted, inserted, and deleted characters) in an alignment of cost at most `cost`.
+    ///
+    /// # Panics
+    /// When `sub` is `0`, or when `extend` is `0` and `cost >= open`.
+    pub fn max_edits(&self, cost: Cost) -> I {
+        let subs = cost / self.sub;
+        let gap = if cost >= self.open + self.extend {
+            (cost - self.open) / self.extend
+        } else {
+            0
+        };
+        subs.max(gap)
+    }
+
+    /// The diagonals `i - j` that an alignment of lengths `n` and `m` with cost at most `cost` can visit,
+    /// or `None` when the cost is less than that of the gap needed to reach diagonal `n - m`.
+    ///
+    /// Leaving the diagonals between `0` and `n - m` by `b` requires at least two gaps of total length `|n - m| + 2b`.
+    ///
+    /// # Panics
+    /// When `extend` is `0` and the cost is at least that of the gap to diagonal `n - m`.
+    pub fn band_for_cost(&self, cost: Cost, n: I, m: I) -> Option<RangeInclusive<I>> {
+        let d = n - m;
+        if cost < self.min_gap(d.abs()) {
+            return None;
+        }
+        let b = ((cost - 2 * self.open - self.extend * d.abs()) / (2 * self.extend)).max(0);
+        Some(d.min(0) - b..=d.max(0) + b)
+    }
+}
+
+/// Costs of substitutions and gaps.
+pub trait Costs {
+    /// The cost of aligning text character `t` to pattern character `p`, usually `0` when they are equal.
+    ///
+    /// # Panics
+    /// May panic when `t` or `p` is not supported by the model, see [`Costs::supports_char`].
+    fn sub_cost(&self, t: Base, p: Base) -> Cost;
+    /// The cost of an insertion of length `len > 0`.
+    fn ins(&self, len: I) -> Cost;
+    /// The cost of a deletion of length `len > 0`.
+    fn del(&self, len: I) -> Cost;
+
+    /// Substitutions and gap characters cost `1`, and matches cost `0`.
+    fn is_unit(&self) -> bool;
+    /// Gap costs are proportional to their length.
+    fn is_linear(&self) -> bool;
+    /// Gap costs are affine with a positive open cost.
+    fn is_affine(&self) -> bool;
+
+    /// A lower bound on the cost of aligning two different characters.
+    fn min_sub(&self) -> Cost;
+    /// The cost of every substitution, when matches are free and all substitutions cost the same.
+    fn uniform_sub(&self) -> Option<Cost>;
+    /// Whether `c` can be passed to [`Costs::sub_cost`]. All characters are supported by default.
+    fn supports_char(&self, _c: Base) -> bool {
+        true
+    }
+    /// A lower bound on the cost of an insertion or deletion of length `len`, or `0` when `len == 0`.
+    fn min_gap(&self, len: I) -> Cost {
+        if len == 0 {
+            0
+        } else {
+            self.ins(len).min(self.del(len))
+        }
+    }
+}
+
+impl Costs for CostModel {
+    fn sub_cost(&self, t: Base, p: Base) -> Cost {
+        if t == p {
+            0
+        } else {
+            self.sub
+        }
+    }
+    fn ins(&self, len: I) -> Cost {
+        CostModel::ins(self, len)
+    }
+    fn del(&self, len: I) -> Cost {
+        CostModel::del(self, len)
+    }
+    fn is_unit(&self) -> bool {
+        CostModel::is_unit(self)
+    }
+    fn is_linear(&self) -> bool {
+        CostModel::is_linear(self)
+    }
+    fn is_affine(&self) -> bool {
+        CostModel::is_affine(self)
+    }
+    fn min_sub(&self) -> Cost {
+        self.sub
+    }
+    fn uniform_sub(&self) -> Option<Cost> {
+        Some(self.sub)
+    }
+}
+
+/// Dual affine gap costs: a gap of length `len` costs the minimum of two affine functions.
+///
+/// Usually `open2 > open1` and `extend2 < extend1`, so that long gaps are cheaper per character.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct DualAffine {
+    pub sub: Cost,
+    pub open1: Cost,
+    pub extend1: Cost,
+    pub open2: Cost,
+    pub extend2: Cost,
+}
+
+impl DualAffine {
+    pub fn new(sub: Cost, open1: Cost, extend1: Cost, open2: Cost, extend2: Cost) -> Self {
+        Self {
+            sub,
+            open1,
+            extend1,
+            open2,
+            extend2,
+        }
+    }
+
+    /// The cost of a gap of given length.
+    pub fn gap(&self, len: I) -> Cost {
+        (self.open1 + len * self.extend1).min(self.open2 + len * self.extend2)
+    }
+}
+
+impl Costs for DualAffine {
+    fn sub_cost(&self, t: Base, p: Base) -> Cost {
+        if t == p {
+            0
+        } else {
+            self.sub
+        }
+    }
+    fn ins(&self, len: I) -> Cost {
+        self.gap(len)
+    }
+    fn del(&self, len: I) -> Cost {
+        self.gap(len)
+    }
+    fn is_unit(&self) -> bool {
+        self.sub == 1 && self.is_linear() && self.gap(1) == 1
+    }
+    /// Dual affine costs are linear when one of the two gap functions is linear and always minimal.
+    fn is_linear(&self) -> bool {
+        (self.open1 == 0 && self.extend1 <= self.extend2)
+            || (self.open2 == 0 && self.extend2 <= self.extend1)
+    }
+    /// Dual affine costs are affine when one of the two gap functions is always minimal.
+    fn is_affine(&self) -> bool {
+        !self.is_linear()
+            && ((self.open1 <= self.open2 && self.extend1 <= self.extend2)
+                || (self.open2 <= self.open1 && self.extend2 <= self.extend1))
+    }
+    fn min_sub(&self) -> Cost {
+        self.sub
+    }
+    fn uniform_sub(&self) -> Option<Cost> {
+        Some(self.sub)
+    }
+}
+
+/// A substitution cost matrix over an alphabet, with affine gap costs.
+///
+/// # Panics
+/// [`Costs::sub_cost`] panics for characters outside the alphabet.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MatrixCost {
+    /// Index of each character in the alphabet, or `u8::MAX` when absent.
+    index: [u8; 256],
+    n: usize,
+    /// `n x n` costs, indexed by `[text char][pattern char]`.
+    costs: Vec<Cost>,
+    pub open: Cost,
+    pub extend: Cost,
+}
+
+impl MatrixCost {
+    /// `costs[a][b]` is the cost of aligning text character `alphabet[a]` to pattern character `alphabet[b]`.
+    pub fn new(alphabet: &[Base], costs: &[&[Cost]], open: Cost, extend: Cost) -> Self {
+        let n = alphabet.len();
+        assert!(n < u8::MAX as usize, "Alphabet is too large.");
+        assert!(
+            costs.len() == n && costs.iter().all(|row| row.len() == n),
+            "Cost matrix must be {n} x {n}."
+        );
+        let mut index = [u8::MAX; 256];
+        for (i, &c) in alphabet.iter().enumerate() {
+            index[c as usize] = i as u8;
+        }
+        Self {
+            index,
+            n,
+            costs: costs.concat(),
+            open,
+            extend,
+        }
+    }
+
+    fn idx(&self, c: Base) -> usize {
+        let i = self.index[c as usize];
+        assert!(
+            i != u8::MAX,
+            "Character {:?} is not in the alphabet.",
+            c as char
+        );
+        i as usize
+    }
+}
+
+impl Costs for MatrixCost {
+    fn sub_cost(&self, t: Base, p: Base) -> Cost {
+        self.costs[self.idx(t) * self.n + self.idx(p)]
+    }
+    fn supports_char(&self, c: Base) -> bool {
+        self.index[c as usize] != u8::MAX
+    }
+    fn ins(&self, len: I) -> Cost {
+        self.open + len * self.extend
+    }
+    fn del(&self, len: I) -> Cost {
+        self.open + len * self.extend
+    }
+    fn is_unit(&self) -> bool {
+        self.is_linear()
+            && self.extend == 1
+            && (0..self.n)
+                .all(|a| (0..self.n).all(|b| self.costs[a * self.n + b] == (a != b) as Cost))
+    }
+    fn is_linear(&self) -> bool {
+        self.open == 0
+    }
+    fn is_affine(&self) -> bool {
+        self.open > 0
+    }
+    /// The minimal off-diagonal entry of the matrix.
+    fn min_sub(&self) -> Cost {
+        (0..self.n)
+            .flat_map(|a| (0..self.n).filter(move |&b| b != a).map(move |b| (a, b)))
+            .map(|(a, b)| self.costs[a * self.n + b])
+            .min()
+            .unwrap_or(0)
+    }
+    fn uniform_sub(&self) -> Option<Cost> {
+        let sub = *self.costs.get(1)?;
+        let uniform = (0..self.n).all(|a| {
+            (0..self.n).all(|b| self.costs[a * self.n + b] == if a == b { 0 } else { sub })
+        });
+        uniform.then_some(sub)
+    }
+}
+
+/// Reasons a string is not a valid [`CostModel`], see the [`FromStr`](std::str::FromStr) implementation of [`CostModel`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseCostModelError {
+    /// Not a preset, and not a list of the expected number of values.
+    InvalidFormat(String),
+    /// A value is not a non-negative integer.
+    InvalidNumber(String),
+    /// The substitution or gap extend cost is `0`.
+    InvalidCost(CostModel),
+}
+
+impl std::fmt::Display for ParseCostModelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseCostModelError::InvalidFormat(s) => write!(
+                f,
+                "Invalid cost model {s:?}, expected e.g. `unit`, `lcs`, `1,0,1`, `linear:2,3` or `affine:4,6,2`"
+            ),
+            ParseCostModelError::InvalidNumber(s) => {
+                write!(f, "Invalid cost {s:?}, expected a non-negative integer")
+            }
+            ParseCostModelError::InvalidCost(cm) => write!(
+                f,
+                "Invalid cost model {cm}: substitution and extend costs must be positive"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseCostModelError {}
+
+impl std::str::FromStr for CostModel {
+    type Err = ParseCostModelError;
+
+    /// Parse a cost model from one of:
+    /// - `unit`: unit costs, `1,0,1`,
+    /// - `lcs`: longest common subsequence, where substitutions are never better than an insertion and deletion, `2,0,1`,
+    /// - `sub,open,extend` or `affine:sub,open,extend`: e.g. `1,0,1` or `affine:4,6,2`,
+    /// - `linear:sub,indel`: e.g. `linear:2,3`, which is `2,0,3`.
+    ///
+    /// Whitespace around values is ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseCostModelError::InvalidFormat(s.to_string());
+        let s = s.trim();
+        let (kind, values) = match s {
+            "unit" => return Ok(CostModel::unit()),
+            "lcs" => return Ok(CostModel::linear(2, 1)),
+            _ => s.split_once(':').unwrap_or(("affine", s)),
+        };
+        let values = values.split(',').map(str::trim).collect::<Vec<_>>();
+        let expected = match kind.trim() {
+            "affine" => 3,
+            "linear" => 2,
+            _ => return Err(invalid()),
+        };
+        if values.len() != expected {
+            return Err(invalid());
+        }
+        let values = values
+            .into_iter()
+            .map(|v| {
+                v.parse::<Cost>()
+                    .ok()
+                    .filter(|&v| v >= 0)
+                    .ok_or_else(|| ParseCostModelError::InvalidNumber(v.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let cm = match values[..] {
+            [sub, open, extend] => CostModel::affine(sub, open, extend),
+            [sub, indel] => CostModel::linear(sub, indel),
+            _ => unreachable!(),
+        };
+        if cm.sub == 0 || cm.extend == 0 {
+            return Err(ParseCostModelError::InvalidCost(cm));
+        }
+        Ok(cm)
+    }
+}
+
+impl std::fmt::Display for CostModel {
+    /// Write the cost model as `sub,open,extend`, which can be parsed back using [`str::parse`].
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{},{}", self.sub, self.open, self.extend)
+    }
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
@@ -92,7 +441,28 @@ pub struct ScoreModel {
 impl ScoreModel {
     const OFFSET: i32 = 1;
 
-    pub fn from_costs(cm: CostModel) -> Self {
+    /// Convert a cost model into an equivalent score model.
+    ///
+    /// # Panics
+    /// When the conversion is not exact, i.e. unless the model is linear or affine
+    /// with equal insertion and deletion costs, a positive extend cost,
+    /// and a single positive substitution cost (see [`Costs::uniform_sub`]).
+    pub fn from_costs(cm: impl Costs) -> Self {
+        let sub = cm
+            .uniform_sub()
+            .filter(|&sub| sub > 0)
+            .expect("ScoreModel requires a single positive substitution cost.");
+        assert!(
+            cm.is_linear() || cm.is_affine(),
+            "ScoreModel requires linear or affine gap costs."
+        );
+        assert!(
+            cm.ins(1) == cm.del(1) && cm.ins(2) == cm.del(2),
+            "ScoreModel requires equal insertion and deletion costs."
+        );
+        let extend = cm.ins(2) - cm.ins(1);
+        assert!(extend > 0, "ScoreModel requires a positive extend cost.");
+        let cm = CostModel::affine(sub, cm.ins(1) - extend, extend);
         let factor;
         if cm.sub > 2 && cm.extend > 1 {
             factor = 1;
@@ -125,3 +495,143 @@ impl ScoreModel {
         s / self.factor
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cigar;
+
+    #[test]
+    fn queries() {
+        assert!(CostModel::unit().is_unit());
+        assert!(DualAffine::new(1, 0, 1, 5, 2).is_unit());
+        let dual = DualAffine::new(1, 2, 2, 6, 1);
+        assert!(!dual.is_linear() && !dual.is_affine());
+        assert_eq!(
+            (dual.min_gap(0), dual.min_gap(1), dual.min_gap(10)),
+            (0, 4, 16)
+        );
+        let m = MatrixCost::new(b"ACGT", &[&[0, 1, 1, 1][..]; 4], 0, 1);
+        assert!(!m.is_unit());
+        assert_eq!(m.min_sub(), 0);
+        let m = MatrixCost::new(b"AC", &[&[0, 1], &[1, 0]], 0, 1);
+        assert!(m.is_unit());
+        assert_eq!(m.min_sub(), 1);
+        assert_eq!(m.uniform_sub(), Some(1));
+        let m = MatrixCost::new(b"AC", &[&[0, 1], &[2, 0]], 0, 1);
+        assert_eq!(m.uniform_sub(), None);
+    }
+
+    #[test]
+    fn lower_bounds_linear() {
+        let cm = CostModel::linear(3, 2);
+        assert_eq!(cm.min_gap(0), 0);
+        assert_eq!(cm.gap_lower_bound(Pos(0, 0), Pos(5, 2)), 6);
+        assert_eq!(cm.gap_lower_bound(Pos(1, 1), Pos(4, 4)), 0);
+        // Gaps are cheaper per edit than substitutions.
+        assert_eq!(cm.max_edits(7), 3);
+        assert_eq!(CostModel::unit().max_edits(5), 5);
+        assert_eq!(cm.band_for_cost(3, 10, 8), None);
+        assert_eq!(cm.band_for_cost(4, 10, 8), Some(0..=2));
+        assert_eq!(cm.band_for_cost(9, 10, 8), Some(-1..=3));
+        assert_eq!(cm.band_for_cost(8, 8, 10), Some(-3..=1));
+    }
+
+    #[test]
+    fn lower_bounds_affine() {
+        let cm = CostModel::affine(2, 5, 1);
+        assert_eq!(cm.gap_lower_bound(Pos(0, 0), Pos(5, 2)), 8);
+        assert_eq!(cm.gap_lower_bound(Pos(5, 2), Pos(6, 6)), 8);
+        // Substitutions are better for low costs, a single long gap for high costs.
+        assert_eq!(cm.max_edits(6), 3);
+        assert_eq!(cm.max_edits(25), 20);
+        assert_eq!(cm.band_for_cost(7, 4, 4), Some(0..=0));
+        assert_eq!(cm.band_for_cost(12, 4, 4), Some(-1..=1));
+        assert_eq!(cm.band_for_cost(6, 4, 5), Some(-1..=0));
+        assert_eq!(cm.band_for_cost(5, 4, 5), None);
+    }
+
+    #[test]
+    fn parse() {
+        let parse = |s: &str| s.parse::<CostModel>();
+        assert_eq!(parse("unit"), Ok(CostModel::unit()));
+        assert_eq!(parse("lcs"), Ok(CostModel::linear(2, 1)));
+        assert_eq!(parse("1,0,1"), Ok(CostModel::unit()));
+        assert_eq!(parse(" 4, 6, 2 "), Ok(CostModel::affine(4, 6, 2)));
+        assert_eq!(parse("affine:4,6,2"), Ok(CostModel::affine(4, 6, 2)));
+        assert_eq!(parse("linear:2,3"), Ok(CostModel::linear(2, 3)));
+        assert_eq!(
+            parse("affine:1,2"),
+            Err(ParseCostModelError::InvalidFormat("affine:1,2".into()))
+        );
+        assert_eq!(
+            parse("edit"),
+            Err(ParseCostModelError::InvalidFormat("edit".into()))
+        );
+        assert_eq!(
+            parse("1,x,1"),
+            Err(ParseCostModelError::InvalidNumber("x".into()))
+        );
+        assert_eq!(
+            parse("1,-1,1"),
+            Err(ParseCostModelError::InvalidNumber("-1".into()))
+        );
+        assert_eq!(
+            parse("0,0,1"),
+            Err(ParseCostModelError::InvalidCost(CostModel::linear(0, 1)))
+        );
+    }
+
+    #[test]
+    fn display_roundtrip() {
+        for cm in [CostModel::unit(), CostModel::affine(4, 6, 2)] {
+            assert_eq!(cm.to_string().parse(), Ok(cm));
+        }
+        assert_eq!(CostModel::linear(2, 3).to_string(), "2,0,3");
+    }
+
+    #[test]
+    fn verify_generic() {
+        let c = Cigar::from_string("1=1X3I");
+        let (text, pattern) = (b"AC", b"AGTTT");
+        assert_eq!(c.verify(&CostModel::affine(2, 3, 1), text, pattern), Ok(8));
+        assert_eq!(
+            c.verify(&DualAffine::new(2, 2, 2, 3, 1), text, pattern),
+            Ok(8)
+        );
+        let m = MatrixCost::new(b"ACGT", &[&[0, 1, 2, 3][..]; 4], 1, 1);
+        assert_eq!(c.verify(&m, text, pattern), Ok(2 + 4));
+        assert!(!m.supports_char(b'N'));
+        assert_eq!(
+            Cigar::from_string("1X").verify(&m, b"N", b"A"),
+            Err("Character not supported by the cost model.")
+        );
+        // Gap characters are not passed to the cost model.
+        assert_eq!(Cigar::from_string("1D").verify(&m, b"N", b""), Ok(2));
+    }
+
+    #[test]
+    fn score_model() {
+        let cm = CostModel::affine(2, 3, 1);
+        assert_eq!(
+            ScoreModel::from_costs(cm),
+            ScoreModel::from_costs(MatrixCost::new(b"AC", &[&[0, 2], &[2, 0]], 3, 1))
+        );
+        let sm = ScoreModel::from_costs(cm);
+        // `1=1X3I` of `AC` and `AGTTT` has cost 8.
+        let score = sm.r#match + sm.sub + sm.open + 3 * sm.extend;
+        assert_eq!(sm.global_cost(score, 2, 5), 8);
+    }
+
+    #[test]
+    #[should_panic(expected = "linear or affine")]
+    fn score_model_dual_affine() {
+        ScoreModel::from_costs(DualAffine::new(1, 2, 2, 6, 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "single positive substitution cost")]
+    fn score_model_matrix() {
+        ScoreModel::from_costs(MatrixCost::new(b"ACGT", &[&[0, 0, 1, 1][..]; 4], 1, 1));
+    }
+}
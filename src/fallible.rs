@@ -0,0 +1,130 @@
+//! Fallible alignment: [`AlignError`] and cooperative cancellation via [`CancellationToken`].
+//!
+//! See [`Aligner::try_align`].
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::*;
+
+/// Reasons an [`Aligner`] can fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AlignError {
+    /// A sequence contains a character the aligner does not support.
+    InvalidAlphabet {
+        /// `0` for the text, `1` for the pattern.
+        seq: usize,
+        pos: usize,
+        base: Base,
+    },
+    /// The alignment would exceed a memory or size limit.
+    ResourceLimit(String),
+    /// The deadline of the [`CancellationToken`] passed.
+    Timeout,
+    /// The [`CancellationToken`] was cancelled.
+    Cancelled,
+    /// The aligner does not support the given cost model.
+    UnsupportedCostModel(CostModel),
+}
+
+impl std::fmt::Display for AlignError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlignError::InvalidAlphabet { seq, pos, base } => write!(
+                f,
+                "Invalid character {:?} at position {pos} of the {}",
+                *base as char,
+                if *seq == 0 { "text" } else { "pattern" }
+            ),
+            AlignError::ResourceLimit(msg) => write!(f, "Resource limit exceeded: {msg}"),
+            AlignError::Timeout => write!(f, "Alignment timed out"),
+            AlignError::Cancelled => write!(f, "Alignment was cancelled"),
+            AlignError::UnsupportedCostModel(cm) => write!(f, "Unsupported cost model {cm:?}"),
+        }
+    }
+}
+
+impl std::error::Error for AlignError {}
+
+/// A cheaply clonable token to cooperatively cancel long-running alignments.
+///
+/// Aligners should regularly call [`CancellationToken::check`], e.g. once per DP row.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl CancellationToken {
+    /// A token that is only cancelled by [`CancellationToken::cancel`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A token that additionally times out after `timeout`.
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            cancelled: Default::default(),
+            deadline: Some(Instant::now() + timeout),
+        }
+    }
+
+    /// Cancel this token and all its clones.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Returns [`AlignError::Cancelled`] or [`AlignError::Timeout`] when the alignment should stop.
+    pub fn check(&self) -> Result<(), AlignError> {
+        if self.is_cancelled() {
+            return Err(AlignError::Cancelled);
+        }
+        if self.deadline.is_some_and(|d| Instant::now() >= d) {
+            return Err(AlignError::Timeout);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancel_clone() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert_eq!(clone.check(), Ok(()));
+        token.cancel();
+        assert_eq!(clone.check(), Err(AlignError::Cancelled));
+    }
+
+    #[test]
+    fn timeout() {
+        let token = CancellationToken::with_timeout(Duration::ZERO);
+        assert_eq!(token.check(), Err(AlignError::Timeout));
+        let mut nw = NW::new(CostModel::unit(), true);
+        assert_eq!(
+            nw.try_align(b"ACGT", b"AGT", &token),
+            Err(AlignError::Timeout)
+        );
+    }
+
+    #[test]
+    fn try_align() {
+        let mut nw = NW::new(CostModel::unit(), true);
+        let aln = nw
+            .try_align(b"ACGT", b"AGT", &CancellationToken::new())
+            .unwrap();
+        assert_eq!(aln.cost, 1);
+        assert_eq!(aln.cigar.unwrap().to_string(), "1=1D2=");
+    }
+}
@@ -0,0 +1,176 @@
+//! Differential fuzzing of two [`Aligner`] implementations.
+//!
+//! [`differential`] generates random and adversarial sequence pairs (repeats,
+//! homopolymers, empty sequences, very skewed lengths) and reports all pairs where
+//! the two aligners return different costs, or where a returned [`Cigar`] is
+//! invalid or inconsistent with the returned cost.
+//! Failures are shrunk to minimal examples using [`shrink`].
+//!
+//! Requires the `testing` feature.
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::testing::{shrink, Failure};
+use crate::*;
+
+/// Parameters for [`differential`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FuzzOptions {
+    /// Number of generated pairs.
+    pub iterations: usize,
+    /// Maximal length of generated sequences.
+    pub max_len: usize,
+    pub seed: u64,
+}
+
+impl Default for FuzzOptions {
+    fn default() -> Self {
+        Self {
+            iterations: 1000,
+            max_len: 100,
+            seed: 0,
+        }
+    }
+}
+
+/// Generate a random or adversarial pair, depending on `rng`.
+pub fn fuzz_pair(rng: &mut StdRng, max_len: usize) -> (Sequence, Sequence) {
+    let len = rng.gen_range(0..=max_len);
+    let seed = rng.gen();
+    let error_rate = rng.gen_range(0.0..=1.0);
+    let pair = |error_model| {
+        let opts = GenerateOptions {
+            error_model,
+            ..GenerateOptions::uniform(len, error_rate)
+        };
+        let (text, pattern, _) = generate_pair(&opts, seed);
+        (text, pattern)
+    };
+    let homopolymer = |rng: &mut StdRng, len: usize| {
+        let mut s = vec![];
+        while s.len() < len {
+            let c = ALPHABET[rng.gen_range(0..ALPHABET.len())];
+            let run = rng.gen_range(1..=len - s.len());
+            s.extend(std::iter::repeat_n(c, run));
+        }
+        s
+    };
+
+    match rng.gen_range(0..6) {
+        0 => pair(ErrorModel::Uniform),
+        1 => pair(ErrorModel::Repeat {
+            unit_len: rng.gen_range(1..=4),
+        }),
+        2 => pair(ErrorModel::LongIndel {
+            max_len: len.max(1),
+        }),
+        3 => {
+            let other = rng.gen_range(0..=max_len);
+            (homopolymer(rng, len), homopolymer(rng, other))
+        }
+        4 => {
+            // Very skewed lengths.
+            let (text, pattern) = pair(ErrorModel::Uniform);
+            let short = pattern[..rng.gen_range(0..=pattern.len().min(3))].to_vec();
+            if rng.gen_bool(0.5) {
+                (text, short)
+            } else {
+                (short, text)
+            }
+        }
+        _ => {
+            let (text, pattern) = pair(ErrorModel::Uniform);
+            if rng.gen_bool(0.5) {
+                (text, vec![])
+            } else {
+                (vec![], pattern)
+            }
+        }
+    }
+}
+
+/// Check a single pair against both aligners.
+///
+/// Returns a description of the first difference or invalid result, if any.
+pub fn compare_pair(
+    a: &mut impl Aligner,
+    b: &mut impl Aligner,
+    cm: &CostModel,
+    text: Seq,
+    pattern: Seq,
+) -> Option<String> {
+    let mut costs = [0; 2];
+    for (i, (cost, cigar)) in [a.align(text, pattern), b.align(text, pattern)]
+        .into_iter()
+        .enumerate()
+    {
+        if let Some(cigar) = cigar {
+            match cigar.verify(cm, text, pattern) {
+                Err(e) => return Some(format!("Aligner {i}: invalid cigar {cigar}: {e}")),
+                Ok(c) if c != cost => {
+                    return Some(format!(
+                        "Aligner {i}: returned cost {cost} differs from cost {c} of cigar {cigar}"
+                    ))
+                }
+                Ok(_) => {}
+            }
+        }
+        costs[i] = cost;
+    }
+    (costs[0] != costs[1]).then(|| format!("Costs differ: {} vs {}", costs[0], costs[1]))
+}
+
+/// Compare aligners created by `factory_a` and `factory_b` on generated pairs.
+///
+/// Returns all failing pairs, each shrunk to a minimal example, without duplicates.
+pub fn differential<A: Aligner, B: Aligner>(
+    cm: &CostModel,
+    mut factory_a: impl FnMut() -> A,
+    mut factory_b: impl FnMut() -> B,
+    opts: &FuzzOptions,
+) -> Vec<Failure> {
+    let rng = &mut StdRng::seed_from_u64(opts.seed);
+    let mut failures: Vec<Failure> = vec![];
+    for _ in 0..opts.iterations {
+        let (text, pattern) = fuzz_pair(rng, opts.max_len);
+        let mut check = |t: Seq, p: Seq| compare_pair(&mut factory_a(), &mut factory_b(), cm, t, p);
+        if let Some(reason) = check(&text, &pattern) {
+            let f = shrink(&text, &pattern, reason, check);
+            if !failures.contains(&f) {
+                failures.push(f);
+            }
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nw_trace_vs_no_trace() {
+        let cm = CostModel::affine(1, 2, 1);
+        let opts = FuzzOptions {
+            iterations: 200,
+            max_len: 30,
+            seed: 1,
+        };
+        let failures = differential(&cm, || NW::new(cm, true), || NW::new(cm, false), &opts);
+        assert!(failures.is_empty(), "{}", failures[0]);
+    }
+
+    #[test]
+    fn finds_cost_model_mismatch() {
+        let cm = CostModel::unit();
+        let other = CostModel::linear(1, 2);
+        let opts = FuzzOptions {
+            iterations: 50,
+            max_len: 30,
+            seed: 2,
+        };
+        let failures = differential(&cm, || NW::new(cm, false), || NW::new(other, false), &opts);
+        assert!(!failures.is_empty());
+        // A single indel is enough to expose the difference.
+        assert!(failures.iter().any(|f| f.text.len() + f.pattern.len() == 1));
+    }
+}
@@ -0,0 +1,212 @@
+//! Seeded generation of random sequence pairs with a known ground-truth alignment.
+//!
+//! A random `text` is mutated into a `pattern` by applying edits with a given
+//! `error_rate` per text position. The returned [`Cigar`] records the applied
+//! edits, so that any optimal aligner must satisfy `cost <= truth.verify(..)`.
+//!
+//! Requires the `generate` feature.
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::*;
+
+/// The alphabet of generated sequences.
+pub const ALPHABET: &[Base] = b"ACGT";
+
+/// The distribution of the random text and the edits applied to it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorModel {
+    /// Uniform random text with independent single-character edits.
+    Uniform,
+    /// The text repeats a random unit of length `unit_len`, with single-character edits.
+    Repeat { unit_len: usize },
+    /// Uniform random text where each indel has a uniform random length in `1..=max_len`.
+    LongIndel { max_len: usize },
+}
+
+/// Parameters for [`generate_pair`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GenerateOptions {
+    /// Length of the text.
+    pub length: usize,
+    /// Probability of an edit at each text position.
+    pub error_rate: f32,
+    /// Relative weight of substitutions.
+    pub sub: f32,
+    /// Relative weight of insertions.
+    pub ins: f32,
+    /// Relative weight of deletions.
+    pub del: f32,
+    pub error_model: ErrorModel,
+}
+
+impl GenerateOptions {
+    /// Uniform errors with equal weights for substitutions, insertions, and deletions.
+    pub fn uniform(length: usize, error_rate: f32) -> Self {
+        Self {
+            length,
+            error_rate,
+            sub: 1.,
+            ins: 1.,
+            del: 1.,
+            error_model: ErrorModel::Uniform,
+        }
+    }
+}
+
+fn random_base(rng: &mut StdRng) -> Base {
+    ALPHABET[rng.gen_range(0..ALPHABET.len())]
+}
+
+fn random_seq(rng: &mut StdRng, len: usize) -> Sequence {
+    (0..len).map(|_| random_base(rng)).collect()
+}
+
+/// Generate a random `(text, pattern, truth)` triple from the given `seed`.
+///
+/// `truth` is a valid alignment of `text` and `pattern`, but not necessarily an optimal one.
+/// Each insertion is followed by a text character that is copied unchanged, so that generation always ends.
+///
+/// # Panics
+/// When `error_rate` is not in `0.0..=1.0`, or the edit weights are negative or sum to `0`.
+pub fn generate_pair(opts: &GenerateOptions, seed: u64) -> (Sequence, Sequence, Cigar) {
+    assert!(
+        (0.0..=1.0).contains(&opts.error_rate),
+        "Error rate must be in 0.0..=1.0, but is {}.",
+        opts.error_rate
+    );
+    let weights = [opts.sub, opts.ins, opts.del];
+    assert!(
+        weights.iter().all(|&w| w >= 0.) && weights.iter().sum::<f32>() > 0.,
+        "Edit weights must be non-negative with a positive sum."
+    );
+    let rng = &mut StdRng::seed_from_u64(seed);
+    let n = opts.length;
+
+    let text = match opts.error_model {
+        ErrorModel::Repeat { unit_len } => {
+            let unit = random_seq(rng, unit_len.max(1));
+            unit.iter().copied().cycle().take(n).collect()
+        }
+        ErrorModel::Uniform | ErrorModel::LongIndel { .. } => random_seq(rng, n),
+    };
+    let max_indel = match opts.error_model {
+        ErrorModel::LongIndel { max_len } => max_len.max(1),
+        _ => 1,
+    };
+    let total = opts.sub + opts.ins + opts.del;
+
+    let mut pattern = vec![];
+    let mut cigar = Cigar::default();
+    let mut i = 0;
+    while i < n {
+        if !rng.gen_bool(opts.error_rate as f64) {
+            pattern.push(text[i]);
+            cigar.push(CigarOp::Match);
+            i += 1;
+            continue;
+        }
+        let r = rng.gen::<f32>() * total;
+        if r < opts.sub {
+            let c = loop {
+                let c = random_base(rng);
+                if c != text[i] {
+                    break c;
+                }
+            };
+            pattern.push(c);
+            cigar.push(CigarOp::Sub);
+            i += 1;
+        } else if r < opts.sub + opts.ins {
+            let len = rng.gen_range(1..=max_indel);
+            pattern.extend(random_seq(rng, len));
+            cigar.push_elem(CigarElem::new(CigarOp::Ins, len as I));
+            pattern.push(text[i]);
+            cigar.push(CigarOp::Match);
+            i += 1;
+        } else {
+            let len = rng.gen_range(1..=max_indel).min(n - i);
+            cigar.push_elem(CigarElem::new(CigarOp::Del, len as I));
+            i += len;
+        }
+    }
+    (text, pattern, cigar)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truth_is_valid() {
+        let cm = CostModel::unit();
+        for error_model in [
+            ErrorModel::Uniform,
+            ErrorModel::Repeat { unit_len: 5 },
+            ErrorModel::LongIndel { max_len: 10 },
+        ] {
+            for seed in 0..20 {
+                let opts = GenerateOptions {
+                    error_model,
+                    ..GenerateOptions::uniform(100, 0.1)
+                };
+                let (text, pattern, truth) = generate_pair(&opts, seed);
+                assert_eq!(text.len(), 100);
+                let truth_cost = truth.verify(&cm, &text, &pattern).unwrap();
+                let (cost, _) = NW::new(cm, false).align(&text, &pattern);
+                assert!(cost <= truth_cost);
+            }
+        }
+    }
+
+    #[test]
+    fn deterministic() {
+        let opts = GenerateOptions::uniform(50, 0.2);
+        assert_eq!(generate_pair(&opts, 42), generate_pair(&opts, 42));
+        assert_ne!(generate_pair(&opts, 42), generate_pair(&opts, 43));
+    }
+
+    #[test]
+    fn only_substitutions() {
+        let opts = GenerateOptions {
+            ins: 0.,
+            del: 0.,
+            ..GenerateOptions::uniform(100, 0.5)
+        };
+        let (text, pattern, truth) = generate_pair(&opts, 0);
+        assert_eq!(text.len(), pattern.len());
+        assert!(truth
+            .ops
+            .iter()
+            .all(|e| matches!(e.op, CigarOp::Match | CigarOp::Sub)));
+    }
+
+    #[test]
+    fn only_insertions() {
+        let opts = GenerateOptions {
+            sub: 0.,
+            del: 0.,
+            ..GenerateOptions::uniform(10, 1.0)
+        };
+        let (text, pattern, truth) = generate_pair(&opts, 0);
+        assert_eq!(pattern.len(), 2 * text.len());
+        assert_eq!(truth.verify(&CostModel::unit(), &text, &pattern), Ok(10));
+    }
+
+    #[test]
+    #[should_panic(expected = "Error rate")]
+    fn invalid_error_rate() {
+        generate_pair(&GenerateOptions::uniform(10, f32::NAN), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Edit weights")]
+    fn zero_weights() {
+        let opts = GenerateOptions {
+            sub: 0.,
+            ins: 0.,
+            del: 0.,
+            ..GenerateOptions::uniform(10, 0.1)
+        };
+        generate_pair(&opts, 0);
+    }
+}
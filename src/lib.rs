@@ -7,25 +7,88 @@
 //!
 //! Affine cost models:
 //! - [`CostModel`],
-//! - [`ScoreModel`].
+//! - [`ScoreModel`],
+//! - [`Costs`] : trait for generic cost models, also implemented by [`DualAffine`] and [`MatrixCost`].
 //!
 //! Alignment path:
 //! - index [`I`] : `i32`,
 //! - position type [`Pos`] : `(I, I)` for (text/ref index, pattern/query index),
-//! - [`Path`] : `Vec<Pos>`.
+//! - [`Path`] : `Vec<Pos>`,
+//! - [`validate_path`] and [`Cigar::try_from_path`] : see [`path`].
 //!
 //! Cigar strings (see [`cigar`] module documentation):
 //! - single character [`CigarOp`] : match/sub/ins/del,
 //! - repeated 'unit' [`CigarElem`] : [`CigarOp`] with length,
-//! - cigar string [`Cigar`] : `Vec<CigarElem>`.
+//! - cigar string [`Cigar`] : `Vec<CigarElem>`,
+//! - cutting cigars at text or pattern coordinates: [`Cigar::slice_text`] and [`Cigar::slice_pattern`], see [`slice`](mod@slice),
+//! - joining partial alignments: [`Cigar::concat`], [`Cigar::join`], and [`chain()`], see [`chain`](mod@chain),
+//! - lifting alignments through a shared sequence: [`Cigar::compose`] and [`Cigar::invert`], see [`compose`],
+//! - shifting gaps to their leftmost or rightmost position: [`Cigar::left_align_gaps`] and [`Cigar::right_align_gaps`], see [`normalize`],
+//! - comparing cigars: [`Cigar::canonical`], [`equivalent`], and [`Cigar::diff`], see [`canonical`],
+//! - [`Cigar::op_costs`] and [`ScoreProfile`] : per-operation costs and X-drop/Z-drop cut points, see [`profile`],
+//! - [`AlignmentStats`] : matches, gaps, identity, and edit distance, see [`stats`],
+//! - SAM `MD` and minimap2 `cs` tags: [`Cigar::to_md`] and [`Cigar::to_cs`], see [`tags`].
+//!
+//! Aligners:
+//! - [`Aligner`] : generic global alignment interface,
+//! - [`AlignOptions`] and [`AlignOutcome`] : cost-only and cost-bounded alignment,
+//! - [`AlignError`] and [`CancellationToken`] : fallible alignment, see [`fallible`],
+//! - [`align_parallel`] : align many pairs using one aligner per thread, see [`batch`],
+//! - [`Capabilities`] and [`Registry`] : describe and select aligners, see [`registry`],
+//! - [`NW`] : reference Needleman-Wunsch/Gotoh implementation, see [`nw`],
+//! - `generate_pair` : random sequence pairs with known alignment, see `generate` (feature `generate`),
+//! - `test_aligner` : conformance tests for aligners, see `testing` (feature `testing`),
+//! - `differential` : differential fuzzing of two aligners, see `fuzz` (feature `testing`),
+//! - `bench` : time, memory, and correctness of aligners, see `bench` (feature `bench`).
+//!
+//! Visualization:
+//! - [`Cigar::render`] : three-line text rendering, see [`render`],
+//! - [`paths_to_svg`] : paths through the DP matrix, see [`svg`].
+pub mod batch;
+#[cfg(any(test, feature = "bench"))]
+pub mod bench;
+pub mod canonical;
+pub mod chain;
 pub mod cigar;
+pub mod compose;
 pub mod cost;
+pub mod fallible;
+#[cfg(any(test, feature = "testing"))]
+pub mod fuzz;
+#[cfg(any(test, feature = "generate"))]
+pub mod generate;
+pub mod normalize;
+pub mod nw;
+pub mod path;
+pub mod profile;
+pub mod registry;
+pub mod render;
+pub mod slice;
+pub mod stats;
+pub mod svg;
+pub mod tags;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 
 // Re-export types for convenience of `use pa_types::*;`.
+pub use batch::*;
+pub use canonical::*;
+pub use chain::*;
 pub use cigar::*;
 pub use cost::*;
+pub use fallible::*;
+#[cfg(any(test, feature = "generate"))]
+pub use generate::*;
+pub use nw::*;
+pub use path::*;
+pub use profile::*;
+pub use registry::*;
+pub use render::*;
+pub use stats::*;
+pub use svg::*;
 
 /// A single base
 // NOTE: This is also part of rust-bio-types.
@@ -117,6 +180,11 @@ impl Pos {
         self.0 - self.1
     }
 
+    /// The number of diagonals between `self` and `other`.
+    pub fn diag_distance(&self, other: Pos) -> I {
+        (self.diag() - other.diag()).abs()
+    }
+
     /// The anti diagonal of position `(i, j)` is `i+j`.
     pub fn anti_diag(&self) -> I {
         self.0 + self.1
@@ -160,10 +228,124 @@ impl Ord for LexPos {
     }
 }
 
+/// Per-call options for [`Aligner::align_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlignOptions {
+    /// Whether to compute a [`Cigar`], or only the cost.
+    pub traceback: bool,
+    /// When set, the aligner may stop early once it knows the cost exceeds this bound.
+    pub max_cost: Option<Cost>,
+}
+
+impl Default for AlignOptions {
+    fn default() -> Self {
+        Self {
+            traceback: true,
+            max_cost: None,
+        }
+    }
+}
+
+/// The cost of an alignment, and optionally its [`Cigar`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Alignment {
+    pub cost: Cost,
+    pub cigar: Option<Cigar>,
+}
+
+/// The result of [`Aligner::align_with`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum AlignOutcome {
+    /// An alignment with cost at most [`AlignOptions::max_cost`].
+    Found(Alignment),
+    /// The cost is larger than [`AlignOptions::max_cost`].
+    ExceedsBound,
+}
+
+impl AlignOutcome {
+    /// The alignment, if one was found within the bound.
+    pub fn found(self) -> Option<Alignment> {
+        match self {
+            AlignOutcome::Found(aln) => Some(aln),
+            AlignOutcome::ExceedsBound => None,
+        }
+    }
+}
+
 /// Generic global pairwise alignment interface.
 pub trait Aligner: std::fmt::Debug {
     /// An alignment of sequences `a` and `b`.
     /// The returned cost is the *non-negative* cost of the alignment.
     /// Costmodel and traceback parameters must be specified on construction of the aligner.
     fn align(&mut self, a: Seq, b: Seq) -> (Cost, Option<Cigar>);
+
+    /// An alignment of `a` and `b` with per-call traceback and cost bound.
+    ///
+    /// The default implementation calls [`Aligner::align`] and filters the result.
+    /// Aligners that can skip traceback or exit early (e.g. band doubling) should override this.
+    fn align_with(&mut self, a: Seq, b: Seq, opts: &AlignOptions) -> AlignOutcome {
+        let (cost, cigar) = self.align(a, b);
+        if opts.max_cost.is_some_and(|max_cost| cost > max_cost) {
+            return AlignOutcome::ExceedsBound;
+        }
+        AlignOutcome::Found(Alignment {
+            cost,
+            cigar: cigar.filter(|_| opts.traceback),
+        })
+    }
+
+    /// A fallible alignment of `a` and `b` that can be cancelled via `cancel`.
+    ///
+    /// Aligners that can run out of resources, reject input, or take long should override this
+    /// and poll [`CancellationToken::check`] regularly.
+    /// The default implementation only checks `cancel` before calling [`Aligner::align`].
+    fn try_align(
+        &mut self,
+        a: Seq,
+        b: Seq,
+        cancel: &CancellationToken,
+    ) -> Result<Alignment, AlignError> {
+        cancel.check()?;
+        let (cost, cigar) = self.align(a, b);
+        Ok(Alignment { cost, cigar })
+    }
+
+    /// The cost models, modes, and alphabets supported by this aligner.
+    ///
+    /// Defaults to [`Capabilities::default`], which does not claim exactness.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
+
+    /// Align all pairs in order. See [`align_parallel`] for a multi-threaded version.
+    fn align_batch<'s>(
+        &mut self,
+        pairs: impl IntoIterator<Item = (Seq<'s>, Seq<'s>)>,
+    ) -> Vec<(Cost, Option<Cigar>)>
+    where
+        Self: Sized,
+    {
+        pairs.into_iter().map(|(a, b)| self.align(a, b)).collect()
+    }
+}
+
+/// Forward to the boxed aligner, e.g. for aligners constructed by a [`Registry`].
+impl<A: Aligner + ?Sized> Aligner for Box<A> {
+    fn align(&mut self, a: Seq, b: Seq) -> (Cost, Option<Cigar>) {
+        (**self).align(a, b)
+    }
+    fn align_with(&mut self, a: Seq, b: Seq, opts: &AlignOptions) -> AlignOutcome {
+        (**self).align_with(a, b, opts)
+    }
+    fn try_align(
+        &mut self,
+        a: Seq,
+        b: Seq,
+        cancel: &CancellationToken,
+    ) -> Result<Alignment, AlignError> {
+        (**self).try_align(a, b, cancel)
+    }
+    fn capabilities(&self) -> Capabilities {
+        (**self).capabilities()
+    }
 }
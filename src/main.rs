@@ -0,0 +1,216 @@
+//! Command line utilities for cigar strings and alignments.
+//!
+//! ```text
+//! pa-types verify 2=1X1I ACGT ACTGA
+//! pa-types verify --cost-model affine:4,6,2 2=1X1I ACG ACTG
+//! pa-types convert 3M ACG ATG
+//! pa-types render 2=1X1I ACG ACTG
+//! pa-types md 2=1X1I ACG ACTG
+//! ```
+use clap::{Parser, Subcommand};
+use pa_types::*;
+
+#[derive(Parser)]
+#[command(author, version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// A cigar string and the sequences it aligns.
+#[derive(clap::Args)]
+struct Input {
+    /// Cigar string, e.g. `3=1X2I` or `4M2I`.
+    cigar: String,
+    /// The text/reference.
+    text: String,
+    /// The pattern/query.
+    pattern: String,
+}
+
+impl Input {
+    /// Parse the cigar without resolving `M`, and check that it aligns the text and pattern.
+    fn parse(&self) -> Result<Cigar, String> {
+        let cigar: Cigar = self.cigar.parse().map_err(|e| format!("{e}"))?;
+        let (text, pattern) = (self.text.as_bytes(), self.pattern.as_bytes());
+        let target = Pos::target(text, pattern);
+        if cigar.delta() != target {
+            return Err(format!(
+                "Cigar ends at {} instead of {target}",
+                cigar.delta()
+            ));
+        }
+        // Check explicit `=` and `X` against the sequences.
+        cigar.verify(&CostModel::unit(), text, pattern)?;
+        Ok(cigar)
+    }
+
+    /// Parse the cigar, or print the error and exit.
+    fn parse_or_exit(&self) -> Cigar {
+        self.parse().unwrap_or_else(|e| {
+            eprintln!("Invalid cigar {}: {e}", self.cigar);
+            std::process::exit(1);
+        })
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Check that the cigar aligns text and pattern, and print its cost.
+    Verify {
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        cm: CostModel,
+        /// Cost model as a preset or `sub,open,extend`, e.g. `unit`, `lcs`, or `affine:4,6,2`.
+        /// Can not be combined with `--sub`, `--open`, and `--extend`.
+        #[arg(long, value_name = "MODEL", conflicts_with_all = ["sub", "open", "extend"])]
+        cost_model: Option<CostModel>,
+    },
+    /// Convert between `=X` and `M` cigar forms.
+    Convert {
+        #[command(flatten)]
+        input: Input,
+        /// Output `M` instead of `=` and `X`.
+        #[arg(long)]
+        m: bool,
+    },
+    /// Print the aligned character pairs, one per line.
+    Pairs {
+        #[command(flatten)]
+        input: Input,
+    },
+    /// Render the alignment as text, markers, and pattern.
+    Render {
+        #[command(flatten)]
+        input: Input,
+        /// Number of columns per block. 0 disables wrapping.
+        #[arg(long, default_value_t = 60)]
+        width: usize,
+        /// Color substitutions and gaps.
+        #[arg(long)]
+        color: bool,
+    },
+    /// Print the minimap2 `cs` tag.
+    Cs {
+        #[command(flatten)]
+        input: Input,
+        /// Use the long form, including matching bases.
+        #[arg(long)]
+        long: bool,
+    },
+    /// Print the SAM `MD` tag.
+    Md {
+        #[command(flatten)]
+        input: Input,
+    },
+}
+
+fn main() {
+    let cli = Cli::parse();
+    match cli.command {
+        Command::Verify {
+            input,
+            cm,
+            cost_model,
+        } => {
+            let cm = cost_model.unwrap_or(cm);
+            let cigar = input.parse_or_exit();
+            match cigar.verify(&cm, input.text.as_bytes(), input.pattern.as_bytes()) {
+                Ok(cost) => println!("{cost}"),
+                Err(e) => {
+                    eprintln!("Invalid cigar {cigar}: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        Command::Convert { input, m } => {
+            let cigar = input
+                .parse_or_exit()
+                .resolve(input.text.as_bytes(), input.pattern.as_bytes());
+            if m {
+                println!("{}", cigar.to_m_string());
+            } else {
+                println!("{cigar}");
+            }
+        }
+        Command::Pairs { input } => {
+            let cigar = input.parse_or_exit();
+            for pair in cigar.to_char_pairs(input.text.as_bytes(), input.pattern.as_bytes()) {
+                let (op, t, p) = match pair {
+                    CigarOpChars::Match(c) => ('=', c, c),
+                    CigarOpChars::Sub(t, p) => ('X', t, p),
+                    CigarOpChars::Del(t) => ('D', t, b'-'),
+                    CigarOpChars::Ins(p) => ('I', b'-', p),
+                };
+                println!("{op} {} {}", t as char, p as char);
+            }
+        }
+        Command::Render {
+            input,
+            width,
+            color,
+        } => {
+            let cigar = input.parse_or_exit();
+            let opts = RenderOptions { width, color };
+            print!(
+                "{}",
+                cigar.render(input.text.as_bytes(), input.pattern.as_bytes(), &opts)
+            );
+        }
+        Command::Cs { input, long } => {
+            let cigar = input.parse_or_exit();
+            println!(
+                "{}",
+                cigar.to_cs(input.text.as_bytes(), input.pattern.as_bytes(), long)
+            );
+        }
+        Command::Md { input } => {
+            let cigar = input.parse_or_exit();
+            println!(
+                "{}",
+                cigar.to_md(input.text.as_bytes(), input.pattern.as_bytes())
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(cigar: &str, text: &str, pattern: &str) -> Input {
+        Input {
+            cigar: cigar.into(),
+            text: text.into(),
+            pattern: pattern.into(),
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let c = input("2=1X1I", "ACG", "ACTG").parse().unwrap();
+        assert_eq!(c.to_string(), "2=1X1I");
+        // `M` is not resolved, so `verify` can check explicit `=` and `X`.
+        let c = input("3M1I", "ACG", "ACTG").parse().unwrap();
+        assert!(!c.is_resolved());
+        assert!(input("3Q", "ACG", "ACG").parse().is_err());
+        assert!(input("3=2", "ACG", "ACG").parse().is_err());
+        assert_eq!(
+            input("1X", "A", "A").parse(),
+            Err("Expected substitution but found match.".to_string())
+        );
+        assert!(input("1=", "A", "C").parse().is_err());
+    }
+
+    #[test]
+    fn parse_overlong() {
+        assert_eq!(
+            input("5M", "ACG", "ACTG").parse(),
+            Err("Cigar ends at Pos(5, 5) instead of Pos(3, 4)".to_string())
+        );
+        assert!(input("2M", "ACG", "ACTG").parse().is_err());
+        assert!(input("2147483647I1I", "A", "C").parse().is_err());
+        assert!(input("2000000000M2000000000D", "A", "C").parse().is_err());
+    }
+}
@@ -0,0 +1,243 @@
+//! Normalizing the position of gaps.
+//!
+//! In homopolymers and tandem repeats, an insertion or deletion can be placed at several offsets
+//! with the same cost. [`Cigar::left_align_gaps`] and [`Cigar::right_align_gaps`] shift each gap
+//! to the leftmost or rightmost such offset, so that alignments from different aligners can be compared.
+//!
+//! Gaps are only shifted over matches, so the cost given by [`Cigar::verify`] never changes.
+//! Gaps of the same type that become adjacent are merged only when this is cost-neutral,
+//! i.e. when the gap open cost is `0`. In that case gaps are also split, and each gap character is
+//! placed individually, so that the result does not depend on the initial position of the gaps.
+use crate::*;
+
+/// Shift all gaps in `ops` as far left as possible. Returns whether anything moved.
+fn left_align(ops: &mut [CigarOp], text: Seq, pattern: Seq, cm: &CostModel) -> bool {
+    if cm.open == 0 {
+        return left_align_linear(ops, text, pattern);
+    }
+    let mut changed = false;
+    let mut idx = 0;
+    let mut pos = Pos(0, 0);
+    while idx < ops.len() {
+        let op = ops[idx];
+        if !matches!(op, CigarOp::Ins | CigarOp::Del) {
+            pos += op.delta();
+            idx += 1;
+            continue;
+        }
+        let len = ops[idx..].iter().take_while(|&&o| o == op).count();
+        // The sequence containing the gapped characters, and the position of the gap in it.
+        let (seq, start) = match op {
+            CigarOp::Del => (text, pos.0),
+            _ => (pattern, pos.1),
+        };
+        // Shift the gap as a block, but do not merge it into a preceding gap of the same type,
+        // since that would save a gap open.
+        let mut k = idx;
+        let mut s = start as usize;
+        while k > 0
+            && ops[k - 1] == CigarOp::Match
+            && seq[s - 1] == seq[s + len - 1]
+            && (k < 2 || ops[k - 2] != op)
+        {
+            ops[k - 1..k + len].rotate_left(1);
+            k -= 1;
+            s -= 1;
+            changed = true;
+        }
+        pos += op.delta() * len as I;
+        idx += len;
+    }
+    changed
+}
+
+/// Left-align gaps when gaps have no open cost, so that gaps can be split and merged freely.
+///
+/// In each maximal run of matches and gaps of one type, the characters of the other sequence
+/// are matched as far right as possible. This placement is unique, so the result does not
+/// depend on the initial position of the gaps.
+fn left_align_linear(ops: &mut [CigarOp], text: Seq, pattern: Seq) -> bool {
+    let mut changed = false;
+    // Moving insertions changes the runs of deletions and vice versa, so repeat until stable.
+    loop {
+        let mut moved = false;
+        for op in [CigarOp::Del, CigarOp::Ins] {
+            // For deletions, pattern characters are matched into the text, and vice versa.
+            let (gapped, other) = match op {
+                CigarOp::Del => (text, pattern),
+                _ => (pattern, text),
+            };
+            let coords = |p: Pos| match op {
+                CigarOp::Del => (p.0 as usize, p.1 as usize),
+                _ => (p.1 as usize, p.0 as usize),
+            };
+            let mut idx = 0;
+            let mut pos = Pos(0, 0);
+            while idx < ops.len() {
+                let len = ops[idx..]
+                    .iter()
+                    .take_while(|&&o| o == op || o == CigarOp::Match)
+                    .count();
+                if len == 0 {
+                    pos += ops[idx].delta();
+                    idx += 1;
+                    continue;
+                }
+                let run = &mut ops[idx..idx + len];
+                let (g0, o0) = coords(pos);
+                for &o in run.iter() {
+                    pos += o.delta();
+                }
+                let (g1, o1) = coords(pos);
+                // Each op in the run consumes one character of `gapped`.
+                let mut new = vec![op; len];
+                let mut g = g1;
+                let mut valid = true;
+                for &c in other[o0..o1].iter().rev() {
+                    while g > g0 && gapped[g - 1] != c {
+                        g -= 1;
+                    }
+                    if g == g0 {
+                        // Not all matches in the run are equal characters.
+                        valid = false;
+                        break;
+                    }
+                    g -= 1;
+                    new[g - g0] = CigarOp::Match;
+                }
+                if valid && run != new.as_slice() {
+                    run.copy_from_slice(&new);
+                    moved = true;
+                }
+                idx += len;
+            }
+        }
+        if !moved {
+            return changed;
+        }
+        changed = true;
+    }
+}
+
+impl Cigar {
+    fn align_gaps(&mut self, text: Seq, pattern: Seq, cm: &CostModel, left: bool) -> bool {
+        let mut ops = self
+            .resolve(text, pattern)
+            .ops
+            .iter()
+            .flat_map(|e| std::iter::repeat_n(e.op, e.cnt as usize))
+            .collect::<Vec<_>>();
+        let changed = if left {
+            left_align(&mut ops, text, pattern, cm)
+        } else {
+            // Right-aligning is left-aligning the reversed alignment.
+            let rev = |s: Seq| s.iter().rev().copied().collect::<Vec<_>>();
+            ops.reverse();
+            let changed = left_align(&mut ops, &rev(text), &rev(pattern), cm);
+            ops.reverse();
+            changed
+        };
+        *self = Cigar::from_ops(ops.into_iter());
+        changed
+    }
+
+    /// Shift every insertion and deletion to its leftmost equal-cost position.
+    ///
+    /// Returns whether any gap moved. The result is resolved, see [`Cigar::resolve`].
+    pub fn left_align_gaps(&mut self, text: Seq, pattern: Seq, cm: &CostModel) -> bool {
+        self.align_gaps(text, pattern, cm, true)
+    }
+
+    /// Shift every insertion and deletion to its rightmost equal-cost position.
+    ///
+    /// Returns whether any gap moved. The result is resolved, see [`Cigar::resolve`].
+    pub fn right_align_gaps(&mut self, text: Seq, pattern: Seq, cm: &CostModel) -> bool {
+        self.align_gaps(text, pattern, cm, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(cigar: &str, text: Seq, pattern: Seq, cm: &CostModel, left: &str, right: &str) {
+        let c = Cigar::from_string(cigar);
+        let cost = c.verify(cm, text, pattern);
+        assert!(cost.is_ok());
+
+        let mut l = c.clone();
+        assert_eq!(l.left_align_gaps(text, pattern, cm), cigar != left);
+        assert_eq!(l.to_string(), left);
+        assert_eq!(l.verify(cm, text, pattern), cost);
+        assert!(!l.clone().left_align_gaps(text, pattern, cm));
+
+        let mut r = c.clone();
+        assert_eq!(r.right_align_gaps(text, pattern, cm), cigar != right);
+        assert_eq!(r.to_string(), right);
+        assert_eq!(r.verify(cm, text, pattern), cost);
+        assert!(!r.clone().right_align_gaps(text, pattern, cm));
+
+        if cm.open == 0 {
+            check_directions(&c, text, pattern, cm);
+        }
+    }
+
+    /// Aligning in one direction and then the other gives the same result as only the latter.
+    fn check_directions(c: &Cigar, text: Seq, pattern: Seq, cm: &CostModel) {
+        let (mut l, mut r) = (c.clone(), c.clone());
+        l.left_align_gaps(text, pattern, cm);
+        r.right_align_gaps(text, pattern, cm);
+        let (mut rl, mut lr) = (r.clone(), l.clone());
+        rl.left_align_gaps(text, pattern, cm);
+        lr.right_align_gaps(text, pattern, cm);
+        assert_eq!(rl, l);
+        assert_eq!(lr, r);
+        let cost = c.verify(cm, text, pattern);
+        assert_eq!(l.verify(cm, text, pattern), cost);
+        assert_eq!(r.verify(cm, text, pattern), cost);
+    }
+
+    #[test]
+    fn homopolymer() {
+        let cm = CostModel::unit();
+        check("2=1D1=", b"AAAC", b"AAC", &cm, "1D3=", "2=1D1=");
+        check("1=1I1=1X", b"AAT", b"AAAC", &cm, "1I2=1X", "2=1I1X");
+    }
+
+    #[test]
+    fn tandem_repeat() {
+        let cm = CostModel::unit();
+        check("2=2D1=", b"ACACG", b"ACG", &cm, "2D3=", "2=2D1=");
+        check("1=2I3=", b"GCAT", b"GCACAT", &cm, "1=2I3=", "3=2I1=");
+    }
+
+    #[test]
+    fn merge_only_when_cost_neutral() {
+        // Merging the two deletions would save a gap open.
+        let affine = CostModel::affine(1, 2, 1);
+        check("1D1=1D1=", b"AAAA", b"AA", &affine, "1D1=1D1=", "1=1D1=1D");
+        let linear = CostModel::linear(1, 1);
+        check("1D1=1D1=", b"AAAA", b"AA", &linear, "2D2=", "2=2D");
+        // The merged gap is shifted further.
+        check("1=1I1=1I", b"CC", b"CACC", &linear, "2I2=", "1=1I1=1I");
+        check("1I1=1I1=", b"CC", b"CCAC", &linear, "1I1=1I1=", "2=2I");
+        // Gaps are split to move characters individually.
+        let unit = CostModel::unit();
+        check("1=1I1=1I", b"AC", b"ACCA", &unit, "1=1I1=1I", "2=2I");
+    }
+
+    #[test]
+    fn random_linear() {
+        let cm = CostModel::linear(1, 1);
+        for error_model in [ErrorModel::Uniform, ErrorModel::Repeat { unit_len: 2 }] {
+            for seed in 0..200 {
+                let opts = GenerateOptions {
+                    error_model,
+                    ..GenerateOptions::uniform(30, 0.3)
+                };
+                let (text, pattern, c) = generate_pair(&opts, seed);
+                check_directions(&c, &text, &pattern, &cm);
+            }
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! Reference Needleman-Wunsch aligner, using Gotoh's three-layer DP for affine costs.
+//!
+//! This is a simple, quadratic time and memory implementation meant as a
+//! ground-truth oracle for testing other [`Aligner`] implementations.
+use crate::*;
+
+/// Cost of unreachable states. Small enough that adding a few costs does not overflow.
+const INF: Cost = Cost::MAX / 4;
+
+/// Quadratic global aligner for any [`CostModel`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NW {
+    pub cm: CostModel,
+    /// When `false`, only the cost is computed and no [`Cigar`] is returned.
+    pub trace: bool,
+}
+
+/// The `(main, del, ins)` DP layers, indexed by `i * (m+1) + j`.
+type Layers = (Vec<Cost>, Vec<Cost>, Vec<Cost>);
+
+/// Reasons to stop filling the DP before reaching the end.
+enum Stop {
+    ExceedsBound,
+    Error(AlignError),
+}
+
+/// The DP layer an alignment ends in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Layer {
+    /// Any operation.
+    Main,
+    /// Ends in a deletion.
+    Del,
+    /// Ends in an insertion.
+    Ins,
+}
+
+impl NW {
+    pub fn new(cm: CostModel, trace: bool) -> Self {
+        Self { cm, trace }
+    }
+
+    /// Fill the three `(n+1) x (m+1)` DP layers for `text` and `pattern`.
+    ///
+    /// Stops as soon as all states in a row exceed `max_cost`,
+    /// or when `cancel` is triggered, which is checked once per row.
+    fn fill(
+        &self,
+        text: Seq,
+        pattern: Seq,
+        max_cost: Option<Cost>,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<Layers, Stop> {
+        let cm = &self.cm;
+        let (n, m) = (text.len(), pattern.len());
+        let idx = |i: usize, j: usize| i * (m + 1) + j;
+        let mut h = vec![INF; (n + 1) * (m + 1)];
+        let mut d = vec![INF; (n + 1) * (m + 1)];
+        let mut ins = vec![INF; (n + 1) * (m + 1)];
+
+        h[0] = 0;
+        for i in 0..=n {
+            if let Some(cancel) = cancel {
+                cancel.check().map_err(Stop::Error)?;
+            }
+            for j in 0..=m {
+                if i > 0 {
+                    d[idx(i, j)] =
+                        (h[idx(i - 1, j)] + cm.open + cm.extend).min(d[idx(i - 1, j)] + cm.extend);
+                }
+                if j > 0 {
+                    ins[idx(i, j)] = (h[idx(i, j - 1)] + cm.open + cm.extend)
+                        .min(ins[idx(i, j - 1)] + cm.extend);
+                }
+                let mut best = d[idx(i, j)].min(ins[idx(i, j)]);
+                if i > 0 && j > 0 {
+                    let s = if text[i - 1] == pattern[j - 1] {
+                        0
+                    } else {
+                        cm.sub
+                    };
+                    best = best.min(h[idx(i - 1, j - 1)] + s);
+                }
+                if i > 0 || j > 0 {
+                    h[idx(i, j)] = best;
+                }
+            }
+            // Every path crosses each row, so the row minimum is a lower bound on the cost.
+            if let Some(max_cost) = max_cost {
+                if h[idx(i, 0)..=idx(i, m)].iter().all(|&c| c > max_cost) {
+                    return Err(Stop::ExceedsBound);
+                }
+            }
+        }
+        Ok((h, d, ins))
+    }
+
+    /// Trace back from `(n, m)` to `(0, 0)` through the DP layers.
+    fn traceback(&self, text: Seq, pattern: Seq, (h, d, ins): &Layers) -> Cigar {
+        let cm = &self.cm;
+        let m = pattern.len();
+        let idx = |i: usize, j: usize| i * (m + 1) + j;
+        let (mut i, mut j) = (text.len(), pattern.len());
+        let mut layer = Layer::Main;
+        let mut ops = vec![];
+        while i > 0 || j > 0 {
+            match layer {
+                Layer::Main => {
+                    let cur = h[idx(i, j)];
+                    if i > 0 && j > 0 {
+                        let eq = text[i - 1] == pattern[j - 1];
+                        let s = if eq { 0 } else { cm.sub };
+                        if cur == h[idx(i - 1, j - 1)] + s {
+                            ops.push(if eq { CigarOp::Match } else { CigarOp::Sub });
+                            i -= 1;
+                            j -= 1;
+                            continue;
+                        }
+                    }
+                    layer = if cur == d[idx(i, j)] {
+                        Layer::Del
+                    } else {
+                        Layer::Ins
+                    };
+                }
+                Layer::Del => {
+                    ops.push(CigarOp::Del);
+                    if d[idx(i, j)] == h[idx(i - 1, j)] + cm.open + cm.extend {
+                        layer = Layer::Main;
+                    }
+                    i -= 1;
+                }
+                Layer::Ins => {
+                    ops.push(CigarOp::Ins);
+                    if ins[idx(i, j)] == h[idx(i, j - 1)] + cm.open + cm.extend {
+                        layer = Layer::Main;
+                    }
+                    j -= 1;
+                }
+            }
+        }
+        ops.reverse();
+        Cigar::from_ops(ops.into_iter())
+    }
+}
+
+impl Aligner for NW {
+    fn align(&mut self, a: Seq, b: Seq) -> (Cost, Option<Cigar>) {
+        let Ok(layers) = self.fill(a, b, None, None) else {
+            unreachable!()
+        };
+        let cost = layers.0[layers.0.len() - 1];
+        let cigar = self.trace.then(|| self.traceback(a, b, &layers));
+        (cost, cigar)
+    }
+
+    fn align_with(&mut self, a: Seq, b: Seq, opts: &AlignOptions) -> AlignOutcome {
+        let Ok(layers) = self.fill(a, b, opts.max_cost, None) else {
+            return AlignOutcome::ExceedsBound;
+        };
+        let cost = layers.0[layers.0.len() - 1];
+        if opts.max_cost.is_some_and(|max_cost| cost > max_cost) {
+            return AlignOutcome::ExceedsBound;
+        }
+        let cigar = opts.traceback.then(|| self.traceback(a, b, &layers));
+        AlignOutcome::Found(Alignment { cost, cigar })
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            exact: true,
+            ..Default::default()
+        }
+    }
+
+    fn try_align(
+        &mut self,
+        a: Seq,
+        b: Seq,
+        cancel: &CancellationToken,
+    ) -> Result<Alignment, AlignError> {
+        let layers = match self.fill(a, b, None, Some(cancel)) {
+            Ok(layers) => layers,
+            Err(Stop::Error(e)) => return Err(e),
+            Err(Stop::ExceedsBound) => unreachable!(),
+        };
+        let cost = layers.0[layers.0.len() - 1];
+        let cigar = self.trace.then(|| self.traceback(a, b, &layers));
+        Ok(Alignment { cost, cigar })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(cm: CostModel, text: &[u8], pattern: &[u8], expected: Cost) {
+        let (cost, cigar) = NW::new(cm, true).align(text, pattern);
+        assert_eq!(cost, expected);
+        assert_eq!(cigar.unwrap().verify(&cm, text, pattern), Ok(cost));
+        assert_eq!(NW::new(cm, false).align(text, pattern), (cost, None));
+    }
+
+    #[test]
+    fn unit() {
+        let cm = CostModel::unit();
+        check(cm, b"", b"", 0);
+        check(cm, b"ACGT", b"", 4);
+        check(cm, b"", b"ACG", 3);
+        check(cm, b"ACGT", b"ACGT", 0);
+        check(cm, b"ACGT", b"AGT", 1);
+        check(cm, b"ACGT", b"TTTT", 3);
+        check(cm, b"kitten", b"sitting", 3);
+    }
+
+    #[test]
+    fn linear() {
+        // Two indels are cheaper than a substitution.
+        check(CostModel::linear(3, 1), b"AC", b"AG", 2);
+        check(CostModel::linear(1, 2), b"ACGT", b"AGT", 2);
+    }
+
+    #[test]
+    fn affine() {
+        let cm = CostModel::affine(1, 2, 1);
+        check(cm, b"ACGTACGT", b"ACGT", 6);
+        // One long gap is cheaper than two short ones.
+        check(cm, b"AAXXAA", b"AAAA", 4);
+        check(cm, b"AXAXA", b"AAA", 5);
+        check(cm, b"", b"AAA", 5);
+    }
+
+    #[test]
+    fn bounded() {
+        let mut nw = NW::new(CostModel::unit(), false);
+        let opts = |max_cost| AlignOptions {
+            traceback: true,
+            max_cost,
+        };
+        let found = nw
+            .align_with(b"ACGT", b"AGT", &opts(Some(1)))
+            .found()
+            .unwrap();
+        assert_eq!(found.cost, 1);
+        assert_eq!(found.cigar.unwrap().to_string(), "1=1D2=");
+        assert_eq!(
+            nw.align_with(b"ACGT", b"TTTT", &opts(Some(2))),
+            AlignOutcome::ExceedsBound
+        );
+        // Exits early after the first row.
+        assert_eq!(
+            nw.align_with(b"AAAA", b"", &opts(Some(0))),
+            AlignOutcome::ExceedsBound
+        );
+        let unbounded = nw.align_with(b"ACGT", b"TTTT", &AlignOptions::default());
+        assert_eq!(unbounded.found().unwrap().cost, 3);
+    }
+}
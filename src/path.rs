@@ -0,0 +1,268 @@
+//! Validation of [`Path`]s, fallible conversion to [`Cigar`], and compressed breakpoint paths.
+//!
+//! [`Cigar::from_path`] panics on invalid input. [`validate_path`] and
+//! [`Cigar::try_from_path`] instead return a [`PathError`] naming the offending step.
+//!
+//! Aligners that only know the breakpoints of a path (e.g. diagonal transition)
+//! can use [`Cigar::try_from_breakpoints`] and [`Cigar::to_breakpoints`].
+use crate::*;
+
+/// Reasons a [`Path`] is invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathError {
+    /// The path contains no positions.
+    Empty,
+    /// The first position is not the expected start.
+    WrongStart { expected: Pos, found: Pos },
+    /// The last position is not the expected end.
+    WrongEnd { expected: Pos, found: Pos },
+    /// Step `index` from `path[index]` to `path[index+1]` is not one of `(1,0)`, `(0,1)`, `(1,1)`.
+    InvalidStep { index: usize, from: Pos, to: Pos },
+    /// Position `index` lies outside the `(text, pattern)` grid.
+    OutOfBounds { index: usize, pos: Pos },
+}
+
+impl std::fmt::Display for PathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathError::Empty => write!(f, "Path is empty"),
+            PathError::WrongStart { expected, found } => {
+                write!(f, "Path starts at {found} instead of {expected}")
+            }
+            PathError::WrongEnd { expected, found } => {
+                write!(f, "Path ends at {found} instead of {expected}")
+            }
+            PathError::InvalidStep { index, from, to } => {
+                write!(f, "Invalid step {index} from {from} to {to}")
+            }
+            PathError::OutOfBounds { index, pos } => {
+                write!(f, "Position {index} at {pos} is out of bounds")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PathError {}
+
+/// Check that `path` runs from `start` to `end` using only unit steps `(1,0)`, `(0,1)`, and `(1,1)`.
+///
+/// Unit steps imply the path is monotone.
+/// For a global alignment, use `start = Pos(0,0)` and `end = Pos::target(text, pattern)`.
+pub fn validate_path(path: &[Pos], start: Pos, end: Pos) -> Result<(), PathError> {
+    let (&first, &last) = path.first().zip(path.last()).ok_or(PathError::Empty)?;
+    if first != start {
+        return Err(PathError::WrongStart {
+            expected: start,
+            found: first,
+        });
+    }
+    validate_steps(path)?;
+    if last != end {
+        return Err(PathError::WrongEnd {
+            expected: end,
+            found: last,
+        });
+    }
+    Ok(())
+}
+
+fn validate_steps(path: &[Pos]) -> Result<(), PathError> {
+    for (index, w) in path.windows(2).enumerate() {
+        if CigarOp::try_from_delta(w[1] - w[0]).is_none() {
+            return Err(PathError::InvalidStep {
+                index,
+                from: w[0],
+                to: w[1],
+            });
+        }
+    }
+    Ok(())
+}
+
+fn validate_bounds(text: Seq, pattern: Seq, path: &[Pos]) -> Result<(), PathError> {
+    if path.is_empty() {
+        return Err(PathError::Empty);
+    }
+    let target = Pos::target(text, pattern);
+    for (index, &pos) in path.iter().enumerate() {
+        if !(Pos(0, 0) <= pos && pos <= target) {
+            return Err(PathError::OutOfBounds { index, pos });
+        }
+    }
+    Ok(())
+}
+
+impl Cigar {
+    /// Fallible version of [`Cigar::from_path`] that also supports paths not starting at `(0,0)`.
+    ///
+    /// The path may start and end at any position inside the `(text, pattern)` grid,
+    /// e.g. for local alignments. Matches are resolved against the corresponding characters.
+    pub fn try_from_path(text: Seq, pattern: Seq, path: &[Pos]) -> Result<Cigar, PathError> {
+        validate_bounds(text, pattern, path)?;
+        validate_steps(path)?;
+
+        let mut c = Cigar::default();
+        for w in path.windows(2) {
+            let op = match CigarOp::try_from_delta(w[1] - w[0]).unwrap() {
+                CigarOp::Match if text[w[0].0 as usize] != pattern[w[0].1 as usize] => CigarOp::Sub,
+                op => op,
+            };
+            c.push(op);
+        }
+        Ok(c)
+    }
+
+    /// Create a Cigar from a compressed path containing only breakpoints.
+    ///
+    /// Consecutive positions may differ by a diagonal jump `(k,k)`, which is resolved into
+    /// matches and substitutions, or by a horizontal `(k,0)` or vertical `(0,k)` jump,
+    /// which become a deletion or insertion of length `k`. Unit steps are also allowed.
+    /// As for [`Cigar::try_from_path`], the path may start at any position.
+    pub fn try_from_breakpoints(text: Seq, pattern: Seq, path: &[Pos]) -> Result<Cigar, PathError> {
+        validate_bounds(text, pattern, path)?;
+        let mut c = Cigar::default();
+        for (index, w) in path.windows(2).enumerate() {
+            let Pos(di, dj) = w[1] - w[0];
+            if di > 0 && di == dj {
+                for k in 0..di {
+                    let Pos(i, j) = w[0] + Pos(k, k);
+                    c.push(if text[i as usize] == pattern[j as usize] {
+                        CigarOp::Match
+                    } else {
+                        CigarOp::Sub
+                    });
+                }
+            } else if di > 0 && dj == 0 {
+                c.push_elem(CigarElem::new(CigarOp::Del, di));
+            } else if di == 0 && dj > 0 {
+                c.push_elem(CigarElem::new(CigarOp::Ins, dj));
+            } else {
+                return Err(PathError::InvalidStep {
+                    index,
+                    from: w[0],
+                    to: w[1],
+                });
+            }
+        }
+        Ok(c)
+    }
+
+    /// The compressed path from `(0,0)` containing only the positions where the direction changes.
+    ///
+    /// Runs of matches and substitutions form a single diagonal jump.
+    /// Inverse of [`Cigar::try_from_breakpoints`].
+    pub fn to_breakpoints(&self) -> Path {
+        let mut pos = Pos(0, 0);
+        let mut path = vec![pos];
+        let mut last_delta = None;
+        for el in &self.ops {
+            if el.cnt == 0 {
+                continue;
+            }
+            pos += el.op.delta() * el.cnt;
+            // Extend the previous jump when it has the same direction.
+            if last_delta == Some(el.op.delta()) {
+                *path.last_mut().unwrap() = pos;
+            } else {
+                path.push(pos);
+            }
+            last_delta = Some(el.op.delta());
+        }
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate() {
+        let path = [Pos(0, 0), Pos(1, 1), Pos(2, 1), Pos(2, 2)];
+        assert_eq!(validate_path(&path, Pos(0, 0), Pos(2, 2)), Ok(()));
+        assert_eq!(
+            validate_path(&[], Pos(0, 0), Pos(0, 0)),
+            Err(PathError::Empty)
+        );
+        assert_eq!(
+            validate_path(&path[1..], Pos(0, 0), Pos(2, 2)),
+            Err(PathError::WrongStart {
+                expected: Pos(0, 0),
+                found: Pos(1, 1)
+            })
+        );
+        assert_eq!(
+            validate_path(&path, Pos(0, 0), Pos(3, 2)),
+            Err(PathError::WrongEnd {
+                expected: Pos(3, 2),
+                found: Pos(2, 2)
+            })
+        );
+        assert_eq!(
+            validate_path(&[Pos(0, 0), Pos(1, 1), Pos(0, 1)], Pos(0, 0), Pos(0, 1)),
+            Err(PathError::InvalidStep {
+                index: 1,
+                from: Pos(1, 1),
+                to: Pos(0, 1)
+            })
+        );
+    }
+
+    #[test]
+    fn try_from_path() {
+        let path = vec![Pos(0, 0), Pos(1, 1), Pos(2, 2), Pos(3, 3), Pos(3, 4)];
+        let c = Cigar::try_from_path(b"aaa", b"aabc", &path).unwrap();
+        assert_eq!(c, Cigar::from_path(b"aaa", b"aabc", &path));
+
+        // Local path not starting at (0,0).
+        let c = Cigar::try_from_path(b"xxab", b"yab", &[Pos(2, 1), Pos(3, 2), Pos(4, 3)]);
+        assert_eq!(c.unwrap().to_string(), "2=");
+
+        assert_eq!(
+            Cigar::try_from_path(b"ab", b"ab", &[Pos(0, 0), Pos(2, 2)]),
+            Err(PathError::InvalidStep {
+                index: 0,
+                from: Pos(0, 0),
+                to: Pos(2, 2)
+            })
+        );
+        assert_eq!(
+            Cigar::try_from_path(b"a", b"a", &[Pos(0, 0), Pos(1, 1), Pos(2, 1)]),
+            Err(PathError::OutOfBounds {
+                index: 2,
+                pos: Pos(2, 1)
+            })
+        );
+    }
+
+    #[test]
+    fn breakpoints() {
+        let (text, pattern) = (b"ACGTTTAC", b"AGGTACCCC");
+        let c = Cigar::from_string("1=1X2=2D2=3I");
+        assert_eq!(c.verify(&CostModel::unit(), text, pattern), Ok(6));
+        let path = c.to_breakpoints();
+        assert_eq!(
+            path,
+            [Pos(0, 0), Pos(4, 4), Pos(6, 4), Pos(8, 6), Pos(8, 9)]
+        );
+        assert_eq!(
+            Cigar::try_from_breakpoints(text, pattern, &path),
+            Ok(c.clone())
+        );
+
+        // Unit steps are valid breakpoints too.
+        assert_eq!(
+            Cigar::try_from_breakpoints(text, pattern, &c.to_path()),
+            Ok(c)
+        );
+        assert_eq!(Cigar::default().to_breakpoints(), [Pos(0, 0)]);
+        assert_eq!(
+            Cigar::try_from_breakpoints(text, pattern, &[Pos(0, 0), Pos(2, 1)]),
+            Err(PathError::InvalidStep {
+                index: 0,
+                from: Pos(0, 0),
+                to: Pos(2, 1)
+            })
+        );
+    }
+}
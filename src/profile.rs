@@ -0,0 +1,113 @@
+//! Running score profiles along an alignment, for spotting poorly aligned regions
+//! and finding X-drop and Z-drop cut points.
+//!
+//! The score at position `(i, j)` with prefix cost `c` is `bonus * (i + j) - c`:
+//! each consumed character of the text or pattern earns `bonus`, so well aligned regions
+//! increase the score and expensive regions decrease it.
+//! For this, substitutions should cost more than `2 * bonus` and gap characters more than `bonus`.
+use crate::*;
+
+/// The score at each position of a path, see [`Cigar::score_profile`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScoreProfile {
+    pub points: Vec<(Pos, Score)>,
+}
+
+impl Cigar {
+    /// The running score `bonus * (i + j) - cost` at each position of the path.
+    pub fn score_profile(
+        &self,
+        cm: &impl Costs,
+        text: Seq,
+        pattern: Seq,
+        bonus: Score,
+    ) -> ScoreProfile {
+        ScoreProfile {
+            points: self
+                .to_path_with_costs(cm, text, pattern)
+                .into_iter()
+                .map(|(pos, cost)| (pos, bonus * pos.anti_diag() - cost))
+                .collect(),
+        }
+    }
+}
+
+impl ScoreProfile {
+    /// The first position with maximal score.
+    pub fn max(&self) -> Option<(Pos, Score)> {
+        self.points
+            .iter()
+            .copied()
+            .reduce(|best, p| if p.1 > best.1 { p } else { best })
+    }
+
+    /// The first point where `drop(best, current)` holds, with `best` the maximum so far.
+    /// Returns the position of that maximum.
+    fn cut(&self, drop: impl Fn((Pos, Score), (Pos, Score)) -> bool) -> Option<Pos> {
+        let mut best = *self.points.first()?;
+        for &p in &self.points {
+            if p.1 > best.1 {
+                best = p;
+            } else if drop(best, p) {
+                return Some(best.0);
+            }
+        }
+        None
+    }
+
+    /// X-drop: cut at the maximum before the score first drops more than `x` below it.
+    ///
+    /// Returns `None` when the score never drops that far.
+    pub fn x_drop(&self, x: Score) -> Option<Pos> {
+        self.cut(|best, p| best.1 - p.1 > x)
+    }
+
+    /// Z-drop as in minimap2: like X-drop, but the allowed drop grows by `extend` per unit
+    /// of diagonal distance from the maximum, so that long gaps are not cut.
+    ///
+    /// Returns `None` when the score never drops that far.
+    pub fn z_drop(&self, z: Score, extend: Score) -> Option<Pos> {
+        self.cut(|best, p| best.1 - p.1 > z + extend * (best.0.diag() - p.0.diag()).abs())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn op_costs() {
+        let cm = DualAffine::new(2, 2, 2, 6, 1);
+        let c = Cigar::from_string("1=1X5I");
+        let costs = c.op_costs(&cm, b"AC", b"AGTTTTT");
+        assert_eq!(
+            costs.iter().map(|&(_, c)| c).collect::<Vec<_>>(),
+            [0, 2, 4, 2, 2, 2, 1]
+        );
+        let path = c.to_path_with_costs(&cm, b"AC", b"AGTTTTT");
+        assert_eq!(path.last(), Some(&(Pos(2, 7), 2 + cm.gap(5))));
+
+        let m = MatrixCost::new(b"AC", &[&[0, 3], &[1, 0]], 1, 1);
+        let c = Cigar::parse("2M", b"AC", b"CC");
+        assert_eq!(
+            c.op_costs(&m, b"AC", b"CC"),
+            [(CigarOp::Sub, 3), (CigarOp::Match, 0)]
+        );
+    }
+
+    #[test]
+    fn drops() {
+        let text = b"ACGTACGTAC";
+        let pattern = b"ACGTTTTTTAC";
+        let c = Cigar::from_string("4=3X1=1I2=");
+        let cm = CostModel::linear(4, 3);
+        assert_eq!(c.verify(&cm, text, pattern), Ok(15));
+        let profile = c.score_profile(&cm, text, pattern, 1);
+        assert_eq!(profile.max(), Some((Pos(4, 4), 8)));
+        // Each substitution reduces the score by 2, down to 2 after the last one.
+        assert_eq!(profile.x_drop(1), Some(Pos(4, 4)));
+        assert_eq!(profile.x_drop(10), None);
+        // Diagonal steps do not increase the allowed drop.
+        assert_eq!(profile.z_drop(1, 100), Some(Pos(4, 4)));
+    }
+}
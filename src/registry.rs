@@ -0,0 +1,203 @@
+//! Aligner [`Capabilities`] and a [`Registry`] of named aligner factories.
+//!
+//! A registry lets tools list the available aligners and pick one that supports a given [`CostModel`]:
+//! ```
+//! use pa_types::*;
+//! let registry = Registry::builtin();
+//! let (name, mut aligner) = registry.select(&CostModel::affine(1, 2, 1)).unwrap();
+//! assert_eq!(name, "nw");
+//! assert_eq!(aligner.align(b"ACGT", b"AGT").0, 3);
+//! ```
+use crate::*;
+
+/// Alignment modes an aligner can support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlignmentMode {
+    /// End-to-end alignment of both sequences.
+    Global,
+    /// The pattern aligns end-to-end against a substring of the text.
+    SemiGlobal,
+    /// Alignment of a substring of the pattern against a substring of the text.
+    Local,
+}
+
+/// Description of what an [`Aligner`] supports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Supports the unit cost model.
+    pub unit: bool,
+    /// Supports linear cost models with arbitrary costs.
+    pub linear: bool,
+    /// Supports affine cost models.
+    pub affine: bool,
+    pub modes: &'static [AlignmentMode],
+    /// Can return a [`Cigar`].
+    pub traceback: bool,
+    /// The supported characters, or `None` when any byte is allowed.
+    pub alphabet: Option<&'static [Base]>,
+    /// Always returns the optimal cost.
+    pub exact: bool,
+}
+
+impl Default for Capabilities {
+    /// All cost models in global mode with traceback and any alphabet,
+    /// but not guaranteed to be exact.
+    fn default() -> Self {
+        Self {
+            unit: true,
+            linear: true,
+            affine: true,
+            modes: &[AlignmentMode::Global],
+            traceback: true,
+            alphabet: None,
+            exact: false,
+        }
+    }
+}
+
+impl Capabilities {
+    /// Whether the cost model `cm` is supported.
+    pub fn supports(&self, cm: &impl Costs) -> bool {
+        if cm.is_unit() {
+            self.unit || self.linear || self.affine
+        } else if cm.is_linear() {
+            self.linear || self.affine
+        } else {
+            cm.is_affine() && self.affine
+        }
+    }
+
+    /// Whether all characters of `seq` are in the supported alphabet.
+    pub fn supports_seq(&self, seq: Seq) -> bool {
+        self.alphabet
+            .is_none_or(|alphabet| seq.iter().all(|c| alphabet.contains(c)))
+    }
+}
+
+/// Construct an aligner for the given cost model.
+pub type AlignerFactory = Box<dyn Fn(CostModel) -> Box<dyn Aligner> + Send + Sync>;
+
+struct Entry {
+    name: String,
+    capabilities: Capabilities,
+    factory: AlignerFactory,
+}
+
+/// Named aligner factories, in order of registration.
+#[derive(Default)]
+pub struct Registry {
+    entries: Vec<Entry>,
+}
+
+impl std::fmt::Debug for Registry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(self.entries.iter().map(|e| (&e.name, &e.capabilities)))
+            .finish()
+    }
+}
+
+impl Registry {
+    /// An empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry containing the aligners in this crate, i.e. `nw` ([`NW`] with traceback).
+    pub fn builtin() -> Self {
+        let mut r = Self::new();
+        r.register(
+            "nw",
+            NW::new(CostModel::unit(), true).capabilities(),
+            |cm| Box::new(NW::new(cm, true)),
+        );
+        r
+    }
+
+    /// Add an aligner. Replaces any existing aligner with the same name.
+    pub fn register(
+        &mut self,
+        name: &str,
+        capabilities: Capabilities,
+        factory: impl Fn(CostModel) -> Box<dyn Aligner> + Send + Sync + 'static,
+    ) {
+        let entry = Entry {
+            name: name.to_string(),
+            capabilities,
+            factory: Box::new(factory),
+        };
+        match self.entries.iter_mut().find(|e| e.name == name) {
+            Some(e) => *e = entry,
+            None => self.entries.push(entry),
+        }
+    }
+
+    /// The names and capabilities of all registered aligners.
+    pub fn list(&self) -> impl Iterator<Item = (&str, &Capabilities)> {
+        self.entries
+            .iter()
+            .map(|e| (e.name.as_str(), &e.capabilities))
+    }
+
+    /// Construct the aligner with the given name, if it exists and supports `cm`.
+    pub fn get(&self, name: &str, cm: &CostModel) -> Option<Box<dyn Aligner>> {
+        self.entries
+            .iter()
+            .find(|e| e.name == name && e.capabilities.supports(cm))
+            .map(|e| (e.factory)(*cm))
+    }
+
+    /// Construct the first registered aligner supporting `cm`, preferring exact aligners.
+    pub fn select(&self, cm: &CostModel) -> Option<(&str, Box<dyn Aligner>)> {
+        let supported = || self.entries.iter().filter(|e| e.capabilities.supports(cm));
+        supported()
+            .find(|e| e.capabilities.exact)
+            .or_else(|| supported().next())
+            .map(|e| (e.name.as_str(), (e.factory)(*cm)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn supports() {
+        let unit_only = Capabilities {
+            linear: false,
+            affine: false,
+            ..Default::default()
+        };
+        assert!(unit_only.supports(&CostModel::unit()));
+        assert!(!unit_only.supports(&CostModel::linear(2, 3)));
+        assert!(!unit_only.supports(&CostModel::affine(1, 2, 1)));
+        assert!(!Capabilities::default().supports(&DualAffine::new(1, 2, 2, 6, 1)));
+        let dna = Capabilities {
+            alphabet: Some(b"ACGT"),
+            ..Default::default()
+        };
+        assert!(dna.supports_seq(b"ACGTTA"));
+        assert!(!dna.supports_seq(b"ACGN"));
+    }
+
+    #[test]
+    fn select() {
+        let mut r = Registry::builtin();
+        let unit_only = Capabilities {
+            linear: false,
+            affine: false,
+            exact: true,
+            ..Default::default()
+        };
+        r.register("unit", unit_only, |cm| Box::new(NW::new(cm, false)));
+        assert_eq!(
+            r.list().map(|(name, _)| name).collect::<Vec<_>>(),
+            ["nw", "unit"]
+        );
+        let cm = CostModel::unit();
+        assert_eq!(r.select(&cm).unwrap().0, "nw");
+        assert!(r.get("unit", &CostModel::affine(1, 2, 1)).is_none());
+        assert_eq!(r.get("unit", &cm).unwrap().align(b"AC", b"A"), (1, None));
+        assert!(r.get("missing", &cm).is_none());
+    }
+}
@@ -0,0 +1,162 @@
+//! Human-readable three-line rendering of alignments, similar to BLAST/EMBOSS pair output.
+//!
+//! ```text
+//! text    1 ACGT-ACG 7
+//!           ||.| |||
+//! pattern 1 ACCTTACG 8
+//! ```
+//!
+//! The middle line contains `|` for matches, `.` for substitutions, and ` ` for gaps.
+//! Gaps in the text and pattern lines are shown as `-`.
+//! Coordinates in the margin are 1-based and inclusive.
+use crate::*;
+
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+/// Options for [`Cigar::render`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// Number of alignment columns per block. `0` disables wrapping.
+    pub width: usize,
+    /// Color substitutions (red) and gaps (yellow) using ANSI escape codes.
+    pub color: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            width: 60,
+            color: false,
+        }
+    }
+}
+
+impl Cigar {
+    /// Render the alignment of `text` and `pattern` as blocks of three lines:
+    /// text, match/mismatch markers, and pattern.
+    ///
+    /// Markers follow the cigar: an incorrect `=` or `X` is shown as written, so that it can be spotted.
+    /// Only unresolved [`CigarOp::AlnMatch`] elements are resolved using the sequences.
+    pub fn render(&self, text: Seq, pattern: Seq, opts: &RenderOptions) -> String {
+        let ops = self
+            .resolve(text, pattern)
+            .ops
+            .iter()
+            .flat_map(|e| std::iter::repeat_n(e.op, e.cnt as usize))
+            .collect::<Vec<_>>();
+        let width = if opts.width == 0 {
+            ops.len().max(1)
+        } else {
+            opts.width
+        };
+        let num_width = text.len().max(pattern.len()).to_string().len();
+
+        let mut out = String::new();
+        let mut pos = Pos(0, 0);
+        for (block_idx, block) in ops.chunks(width).enumerate() {
+            let start = pos;
+            let mut t = String::new();
+            let mut m = String::new();
+            let mut p = String::new();
+            for &op in block {
+                let (tc, mc, pc) = match op {
+                    CigarOp::Match => (text[pos.0 as usize], '|', pattern[pos.1 as usize]),
+                    CigarOp::Sub => (text[pos.0 as usize], '.', pattern[pos.1 as usize]),
+                    CigarOp::Del => (text[pos.0 as usize], ' ', b'-'),
+                    CigarOp::Ins => (b'-', ' ', pattern[pos.1 as usize]),
+                    CigarOp::AlnMatch => unreachable!("AlnMatch is resolved above"),
+                };
+                let color = match op {
+                    _ if !opts.color => None,
+                    CigarOp::Match | CigarOp::AlnMatch => None,
+                    CigarOp::Sub => Some(RED),
+                    CigarOp::Del | CigarOp::Ins => Some(YELLOW),
+                };
+                if let Some(color) = color {
+                    t.push_str(color);
+                    p.push_str(color);
+                }
+                t.push(tc as char);
+                m.push(mc);
+                p.push(pc as char);
+                if color.is_some() {
+                    t.push_str(RESET);
+                    p.push_str(RESET);
+                }
+                pos += op.delta();
+            }
+
+            // 1-based inclusive coordinates. Blocks without residues of a
+            // sequence show the last position before the block twice.
+            let range = |start: I, end: I| {
+                if start == end {
+                    (end, end)
+                } else {
+                    (start + 1, end)
+                }
+            };
+            let (ts, te) = range(start.0, pos.0);
+            let (ps, pe) = range(start.1, pos.1);
+
+            if block_idx > 0 {
+                out.push('\n');
+            }
+            out.push_str(&format!("text    {ts:>num_width$} {t} {te}\n"));
+            out.push_str(&format!("        {:>num_width$} {}\n", "", m.trim_end()));
+            out.push_str(&format!("pattern {ps:>num_width$} {p} {pe}\n"));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_single_block() {
+        let c = Cigar::from_string("2=1X1=1I3=");
+        let s = c.render(b"ACGTACG", b"ACCTTACG", &RenderOptions::default());
+        assert_eq!(
+            s,
+            "text    1 ACGT-ACG 7\n          ||.| |||\npattern 1 ACCTTACG 8\n"
+        );
+    }
+
+    #[test]
+    fn render_wrapped() {
+        let c = Cigar::from_string("2=2D2=");
+        let opts = RenderOptions {
+            width: 3,
+            color: false,
+        };
+        let s = c.render(b"ACGTAC", b"ACAC", &opts);
+        assert_eq!(
+            s,
+            "text    1 ACG 3\n          ||\npattern 1 AC- 2\n\n\
+             text    4 TAC 6\n           ||\npattern 3 -AC 4\n"
+        );
+    }
+
+    #[test]
+    fn render_color() {
+        let c = Cigar::from_string("1X");
+        let opts = RenderOptions {
+            width: 60,
+            color: true,
+        };
+        let s = c.render(b"A", b"C", &opts);
+        assert!(s.contains("\x1b[31mA\x1b[0m"));
+        assert!(s.contains("\x1b[31mC\x1b[0m"));
+    }
+
+    #[test]
+    fn render_keeps_explicit_ops() {
+        let s = Cigar::from_string("2=").render(b"AC", b"AG", &RenderOptions::default());
+        assert_eq!(s, "text    1 AC 2\n          ||\npattern 1 AG 2\n");
+        let s = Cigar::from_string("2M").render(b"AC", b"AG", &RenderOptions::default());
+        assert_eq!(s, "text    1 AC 2\n          |.\npattern 1 AG 2\n");
+    }
+}
@@ -0,0 +1,146 @@
+//! Cutting a [`Cigar`] at text or pattern coordinates.
+//!
+//! A cut at text coordinate `i` happens at the *first* position on the path with text coordinate `i`.
+//! This means that insertions directly at the cut go to the right part,
+//! and that a cut inside a deletion or a diagonal run splits the corresponding [`CigarElem`].
+//! Cuts at pattern coordinates work the same, with the roles of insertions and deletions swapped.
+use std::ops::Range;
+
+use crate::*;
+
+/// The coordinate a cut is made on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    Text,
+    Pattern,
+}
+
+impl Axis {
+    fn get(self, pos: Pos) -> I {
+        match self {
+            Axis::Text => pos.0,
+            Axis::Pattern => pos.1,
+        }
+    }
+}
+
+impl Cigar {
+    fn split_at(&self, axis: Axis, x: I) -> (Cigar, Cigar, Pos) {
+        let mut left = Cigar::default();
+        let mut right = Cigar::default();
+        let mut pos = Pos(0, 0);
+        let mut ops = self.ops.iter();
+        for &el in ops.by_ref() {
+            let remaining = x - axis.get(pos);
+            if remaining <= 0 {
+                right.push_elem(el);
+                break;
+            }
+            let step = axis.get(el.op.delta());
+            if step == 1 && el.cnt > remaining {
+                left.push_elem(CigarElem::new(el.op, remaining));
+                right.push_elem(CigarElem::new(el.op, el.cnt - remaining));
+                pos += el.op.delta() * remaining;
+                break;
+            }
+            left.push_elem(el);
+            pos += el.op.delta() * el.cnt;
+        }
+        for &el in ops {
+            right.push_elem(el);
+        }
+        (left, right, pos)
+    }
+
+    /// Split the cigar at the first position with text coordinate `i`.
+    ///
+    /// Returns the two parts and the position of the cut.
+    pub fn split_at_text(&self, i: I) -> (Cigar, Cigar, Pos) {
+        self.split_at(Axis::Text, i)
+    }
+
+    /// Split the cigar at the first position with pattern coordinate `j`.
+    ///
+    /// Returns the two parts and the position of the cut.
+    pub fn split_at_pattern(&self, j: I) -> (Cigar, Cigar, Pos) {
+        self.split_at(Axis::Pattern, j)
+    }
+
+    fn slice(&self, axis: Axis, range: Range<I>) -> (Cigar, Pos, Pos) {
+        let (_, right, start) = self.split_at(axis, range.start);
+        let (mid, _, len) = right.split_at(axis, range.end - axis.get(start));
+        (mid, start, start + len)
+    }
+
+    /// The part of the alignment covering text interval `range`, and the corresponding pattern range.
+    pub fn slice_text(&self, range: Range<I>) -> (Cigar, Range<I>) {
+        let (c, start, end) = self.slice(Axis::Text, range);
+        (c, start.1..end.1)
+    }
+
+    /// The part of the alignment covering pattern interval `range`, and the corresponding text range.
+    pub fn slice_pattern(&self, range: Range<I>) -> (Cigar, Range<I>) {
+        let (c, start, end) = self.slice(Axis::Pattern, range);
+        (c, start.0..end.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_inside_elem() {
+        let c = Cigar::from_string("4=2D3=");
+        let (l, r, pos) = c.split_at_text(2);
+        assert_eq!(
+            (l.to_string(), r.to_string(), pos),
+            ("2=".into(), "2=2D3=".into(), Pos(2, 2))
+        );
+        // Inside the deletion.
+        let (l, r, pos) = c.split_at_text(5);
+        assert_eq!(
+            (l.to_string(), r.to_string(), pos),
+            ("4=1D".into(), "1D3=".into(), Pos(5, 4))
+        );
+        // The deletion is entirely left of the pattern cut.
+        let (l, r, pos) = c.split_at_pattern(4);
+        assert_eq!(
+            (l.to_string(), r.to_string(), pos),
+            ("4=".into(), "2D3=".into(), Pos(4, 4))
+        );
+    }
+
+    #[test]
+    fn split_at_ends() {
+        let c = Cigar::from_string("1I2=1I");
+        let (l, r, pos) = c.split_at_text(0);
+        assert_eq!(
+            (l, r.clone(), pos),
+            (Cigar::default(), c.clone(), Pos(0, 0))
+        );
+        let (l, r, pos) = c.split_at_text(2);
+        assert_eq!(
+            (l.to_string(), r.to_string(), pos),
+            ("1I2=".into(), "1I".into(), Pos(2, 3))
+        );
+        let (l, r, pos) = c.split_at_text(10);
+        assert_eq!((l, r, pos), (c, Cigar::default(), Pos(2, 4)));
+    }
+
+    #[test]
+    fn slice() {
+        //  text: ACGT--ACGT
+        //   pat: A-GTTTAC-T
+        let c = Cigar::from_string("1=1D2=2I2=1D1=");
+        let (s, pattern_range) = c.slice_text(1..6);
+        assert_eq!(s.to_string(), "1D2=2I2=");
+        assert_eq!(pattern_range, 1..7);
+        let (s, text_range) = c.slice_pattern(3..5);
+        assert_eq!(s.to_string(), "2I");
+        assert_eq!(text_range, 4..4);
+        let (s, text_range) = c.slice_pattern(2..8);
+        assert_eq!(s.to_string(), "1=2I2=1D1=");
+        assert_eq!(text_range, 3..8);
+    }
+}
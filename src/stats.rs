@@ -0,0 +1,123 @@
+//! Summary statistics of an alignment: [`AlignmentStats`].
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Counts and identities of an alignment, as computed by [`Cigar::stats`].
+///
+/// Gap counts are in characters; `gap_opens` counts runs of consecutive insertions or deletions.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct AlignmentStats {
+    pub matches: usize,
+    pub mismatches: usize,
+    /// Unresolved [`CigarOp::AlnMatch`] (`M`) columns, which may be matches or mismatches.
+    pub aligned_unresolved: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+    pub gap_opens: usize,
+    /// Number of alignment columns.
+    pub length: usize,
+    /// Mismatches plus inserted and deleted characters.
+    /// Only a lower bound when there are unresolved columns.
+    pub edit_distance: usize,
+    /// Matches divided by the alignment length, as reported by BLAST.
+    /// `None` when there are unresolved columns.
+    pub blast_identity: Option<f64>,
+    /// Matches divided by the number of matches, mismatches, and gap opens, as reported by minimap2.
+    /// `None` when there are unresolved columns.
+    pub gap_compressed_identity: Option<f64>,
+}
+
+impl Cigar {
+    /// Statistics of this alignment.
+    ///
+    /// Identities of the empty alignment are `1`.
+    /// [`CigarOp::AlnMatch`] columns are counted in `aligned_unresolved`, and then identities are unknown.
+    /// Use [`Cigar::stats_with_seqs`] to resolve them.
+    pub fn stats(&self) -> AlignmentStats {
+        let mut s = AlignmentStats::default();
+        let mut last = None;
+        for el in &self.ops {
+            if el.cnt == 0 {
+                continue;
+            }
+            let cnt = el.cnt as usize;
+            match el.op {
+                CigarOp::Match => s.matches += cnt,
+                CigarOp::Sub => s.mismatches += cnt,
+                CigarOp::Ins => s.insertions += cnt,
+                CigarOp::Del => s.deletions += cnt,
+                CigarOp::AlnMatch => s.aligned_unresolved += cnt,
+            }
+            if matches!(el.op, CigarOp::Ins | CigarOp::Del) && last != Some(el.op) {
+                s.gap_opens += 1;
+            }
+            last = Some(el.op);
+        }
+        s.length = s.matches + s.mismatches + s.aligned_unresolved + s.insertions + s.deletions;
+        s.edit_distance = s.mismatches + s.insertions + s.deletions;
+        let ratio = |num: usize, den: usize| {
+            if s.aligned_unresolved > 0 {
+                None
+            } else if den == 0 {
+                Some(1.0)
+            } else {
+                Some(num as f64 / den as f64)
+            }
+        };
+        s.blast_identity = ratio(s.matches, s.length);
+        s.gap_compressed_identity = ratio(s.matches, s.matches + s.mismatches + s.gap_opens);
+        s
+    }
+
+    /// Statistics of this alignment, after resolving matches against `text` and `pattern`.
+    pub fn stats_with_seqs(&self, text: Seq, pattern: Seq) -> AlignmentStats {
+        self.resolve(text, pattern).stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats() {
+        let s = Cigar::from_string("3=1X2I1=1D").stats();
+        assert_eq!(
+            (s.matches, s.mismatches, s.insertions, s.deletions),
+            (4, 1, 2, 1)
+        );
+        assert_eq!((s.gap_opens, s.length, s.edit_distance), (2, 8, 4));
+        assert_eq!(s.blast_identity, Some(0.5));
+        assert_eq!(s.gap_compressed_identity, Some(4.0 / 7.0));
+
+        let s = Cigar::default().stats();
+        assert_eq!((s.length, s.blast_identity), (0, Some(1.0)));
+    }
+
+    #[test]
+    fn unresolved() {
+        let s = Cigar::from_string("3M1I1=").stats();
+        assert_eq!((s.matches, s.aligned_unresolved, s.insertions), (1, 3, 1));
+        assert_eq!((s.length, s.edit_distance), (5, 1));
+        assert_eq!(s.blast_identity, None);
+        assert_eq!(s.gap_compressed_identity, None);
+    }
+
+    #[test]
+    fn stats_with_seqs() {
+        let c = Cigar::parse("4M", b"ACGT", b"ACCT");
+        assert_eq!(c.stats_with_seqs(b"ACGT", b"ACCT").mismatches, 1);
+        let c = Cigar::from_path_unresolved(&vec![Pos(0, 0), Pos(1, 1), Pos(2, 2)]);
+        let s = c.stats_with_seqs(b"AC", b"AG");
+        assert_eq!((s.matches, s.mismatches), (1, 1));
+    }
+
+    #[test]
+    fn serde() {
+        let s = Cigar::from_string("2=1D").stats();
+        let json = serde_json::to_string(&s).unwrap();
+        assert!(json.contains("\"gap_opens\":1"));
+        assert_eq!(serde_json::from_str::<AlignmentStats>(&json).unwrap(), s);
+    }
+}
@@ -0,0 +1,143 @@
+//! SVG visualization of alignment paths through the `(text, pattern)` DP matrix.
+//!
+//! The text runs left to right (x axis) and the pattern top to bottom (y axis),
+//! so a [`Pos`] `(i, j)` is drawn at `(i, j) * cell_size`.
+//! Each step of a path is coloured by its [`CigarOp`].
+//! Multiple paths are drawn on top of each other, later paths with a different dash pattern.
+use std::fmt::Write;
+
+use crate::*;
+
+/// Options for [`paths_to_svg`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SvgOptions {
+    /// Size in pixels of a single cell of the DP matrix.
+    pub cell_size: u32,
+    /// When set, shade the cells on each path by their cost from [`Cigar::to_path_with_costs`].
+    pub cost_model: Option<CostModel>,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            cell_size: 10,
+            cost_model: None,
+        }
+    }
+}
+
+/// The stroke colour used for each [`CigarOp`].
+pub fn op_color(op: CigarOp) -> &'static str {
+    match op {
+        CigarOp::Match => "black",
+        CigarOp::Sub => "red",
+        CigarOp::Del => "blue",
+        CigarOp::Ins => "green",
+        CigarOp::AlnMatch => "gray",
+    }
+}
+
+/// Dash patterns used to distinguish overlapping paths.
+const DASHES: [&str; 4] = ["none", "6,3", "2,2", "8,2,2,2"];
+
+/// Draw the paths of the given `cigars` in the DP matrix of `text` and `pattern`.
+///
+/// Returns a standalone `<svg>` element that can be embedded directly in HTML.
+pub fn paths_to_svg(text: Seq, pattern: Seq, cigars: &[&Cigar], opts: &SvgOptions) -> String {
+    let (text_len, pattern_len) = (text.len(), pattern.len());
+    let s = opts.cell_size as I;
+    let margin = s;
+    let w = text_len as I * s + 2 * margin;
+    let h = pattern_len as I * s + 2 * margin;
+    let xy = |p: Pos| (margin + p.0 * s, margin + p.1 * s);
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#
+    )
+    .unwrap();
+    writeln!(
+        out,
+        r#"<rect x="{margin}" y="{margin}" width="{}" height="{}" fill="white" stroke="gray"/>"#,
+        text_len as I * s,
+        pattern_len as I * s
+    )
+    .unwrap();
+
+    if let Some(cm) = opts.cost_model {
+        for cigar in cigars {
+            let costs = cigar.to_path_with_costs(&cm, text, pattern);
+            let max_cost = costs.iter().map(|&(_, c)| c).max().unwrap_or(0).max(1);
+            writeln!(out, r#"<g class="costs">"#).unwrap();
+            for (pos, cost) in costs {
+                let (x, y) = xy(pos);
+                writeln!(
+                    out,
+                    r#"<rect x="{}" y="{}" width="{s}" height="{s}" fill="orange" fill-opacity="{:.3}"/>"#,
+                    x - s / 2,
+                    y - s / 2,
+                    cost as f32 / max_cost as f32
+                )
+                .unwrap();
+            }
+            writeln!(out, "</g>").unwrap();
+        }
+    }
+
+    for (k, cigar) in cigars.iter().enumerate() {
+        writeln!(
+            out,
+            r#"<g class="path" id="path-{k}" stroke-width="2" stroke-dasharray="{}">"#,
+            DASHES[k % DASHES.len()]
+        )
+        .unwrap();
+        let mut pos = Pos(0, 0);
+        for el in &cigar.ops {
+            let end = pos + el.op.delta() * el.cnt;
+            let (x1, y1) = xy(pos);
+            let (x2, y2) = xy(end);
+            writeln!(
+                out,
+                r#"<line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}" stroke="{}"/>"#,
+                op_color(el.op)
+            )
+            .unwrap();
+            pos = end;
+        }
+        writeln!(out, "</g>").unwrap();
+    }
+    writeln!(out, "</svg>").unwrap();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn svg_lines_per_elem() {
+        let a = Cigar::from_string("2=1X1I");
+        let b = Cigar::from_string("1=1D1I2=");
+        let svg = paths_to_svg(b"ACG", b"ACTA", &[&a, &b], &SvgOptions::default());
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<line").count(), 3 + 4);
+        assert!(svg.contains(r#"id="path-1""#));
+        // End of the first path.
+        assert!(svg.contains(r#"x2="40" y2="50""#));
+    }
+
+    #[test]
+    fn svg_cost_shading() {
+        let a = Cigar::from_string("2=1X1I");
+        let opts = SvgOptions {
+            cost_model: Some(CostModel::unit()),
+            ..Default::default()
+        };
+        let svg = paths_to_svg(b"ACG", b"ACTA", &[&a], &opts);
+        // One shaded cell per position on the path.
+        assert_eq!(svg.matches("fill=\"orange\"").count(), 5);
+        assert!(svg.contains(r#"fill-opacity="1.000""#));
+    }
+}
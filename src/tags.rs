@@ -0,0 +1,125 @@
+//! Conversion of a [`Cigar`] to the SAM `MD` tag and the minimap2 `cs` tag.
+//!
+//! As for cigars, the text is the reference and the pattern is the query.
+//! See <https://samtools.github.io/hts-specs/SAMtags.pdf> and
+//! <https://lh3.github.io/minimap2/minimap2.html#10>.
+use itertools::Itertools;
+use std::fmt::Write;
+
+use crate::*;
+
+impl Cigar {
+    /// Format the cigar with `=` and `X` merged into `M`, e.g. `5M1I2M`.
+    pub fn to_m_string(&self) -> String {
+        let mut s = String::new();
+        for (is_m, group) in &self
+            .ops
+            .iter()
+            .chunk_by(|e| matches!(e.op, CigarOp::Match | CigarOp::Sub | CigarOp::AlnMatch))
+        {
+            let mut group = group.peekable();
+            let op = group.peek().unwrap().op;
+            let cnt: I = group.map(|e| e.cnt).sum();
+            let c = if is_m { 'M' } else { op.to_char() };
+            write!(s, "{cnt}{c}").unwrap();
+        }
+        s
+    }
+
+    /// The SAM `MD` tag: matching runs, mismatching text bases, and `^` followed by deleted text bases.
+    ///
+    /// Insertions are not represented.
+    pub fn to_md(&self, text: Seq, pattern: Seq) -> String {
+        let mut s = String::new();
+        let mut matches = 0;
+        let mut in_del = false;
+        for c in self.to_char_pairs(text, pattern) {
+            match c {
+                CigarOpChars::Match(_) => matches += 1,
+                CigarOpChars::Sub(t, _) => {
+                    write!(s, "{matches}{}", t.to_ascii_uppercase() as char).unwrap();
+                    matches = 0;
+                }
+                CigarOpChars::Del(t) => {
+                    if !in_del {
+                        write!(s, "{matches}^").unwrap();
+                        matches = 0;
+                    }
+                    s.push(t.to_ascii_uppercase() as char);
+                }
+                CigarOpChars::Ins(_) => {}
+            }
+            in_del = matches!(c, CigarOpChars::Del(_));
+        }
+        write!(s, "{matches}").unwrap();
+        s
+    }
+
+    /// The minimap2 `cs` tag.
+    ///
+    /// The short form writes matching runs as `:<len>`, the long form as `=<bases>`.
+    /// Substitutions are `*<text><pattern>`, insertions `+<pattern bases>`, and deletions `-<text bases>`.
+    /// Explicit `=` and `X` elements are written as given; only [`CigarOp::AlnMatch`] is resolved.
+    pub fn to_cs(&self, text: Seq, pattern: Seq, long: bool) -> String {
+        let lower = |seq: Seq| seq_to_string(&seq.to_ascii_lowercase());
+        let mut s = String::new();
+        let mut pos = Pos(0, 0);
+        for &CigarElem { op, cnt } in &self.resolve(text, pattern).ops {
+            let (i, j) = (pos.0 as usize, pos.1 as usize);
+            match op {
+                CigarOp::Match if long => write!(
+                    s,
+                    "={}",
+                    seq_to_string(&text[i..i + cnt as usize].to_ascii_uppercase())
+                )
+                .unwrap(),
+                CigarOp::Match => write!(s, ":{cnt}").unwrap(),
+                CigarOp::Sub => {
+                    for k in 0..cnt as usize {
+                        write!(s, "*{}", lower(&[text[i + k], pattern[j + k]])).unwrap();
+                    }
+                }
+                CigarOp::Ins => write!(s, "+{}", lower(&pattern[j..j + cnt as usize])).unwrap(),
+                CigarOp::Del => write!(s, "-{}", lower(&text[i..i + cnt as usize])).unwrap(),
+                CigarOp::AlnMatch => unreachable!("AlnMatch is resolved above"),
+            }
+            pos += op.delta() * cnt;
+        }
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn m_string() {
+        let c = Cigar::from_string("2=1X1I3=2D");
+        assert_eq!(c.to_m_string(), "3M1I3M2D");
+        let c = Cigar::from_string("1M1X1I");
+        assert_eq!(c.to_m_string(), "2M1I");
+    }
+
+    #[test]
+    fn md() {
+        //          text: ACGTA-CCGT
+        //       pattern: ACTTAGC--T
+        let c = Cigar::from_string("2=1X2=1I1=2D1=");
+        assert_eq!(c.to_md(b"ACGTACCGT", b"ACTTAGCT"), "2G3^CG1");
+        // Deletion directly followed by a mismatch.
+        let c = Cigar::from_string("1D1X");
+        assert_eq!(c.to_md(b"AC", b"G"), "0^A0C0");
+    }
+
+    #[test]
+    fn cs() {
+        let c = Cigar::from_string("2=1X2=1I1=2D1=");
+        let (text, pattern) = (b"ACGTACCGT", b"ACTTAGCT");
+        assert_eq!(c.to_cs(text, pattern, false), ":2*gt:2+g:1-cg:1");
+        assert_eq!(c.to_cs(text, pattern, true), "=AC*gt=TA+g=C-cg=T");
+        // Explicit matches are kept, unresolved ones are resolved.
+        assert_eq!(Cigar::from_string("2=").to_cs(b"AC", b"AG", false), ":2");
+        assert_eq!(Cigar::from_string("2M").to_cs(b"AC", b"AG", false), ":1*cg");
+    }
+}
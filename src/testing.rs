@@ -0,0 +1,219 @@
+//! Conformance tests for [`Aligner`] implementations.
+//!
+//! [`test_aligner`] runs a battery of edge cases and random sequence pairs and checks that:
+//! - a returned [`Cigar`] passes [`Cigar::verify`],
+//! - the cost of the returned [`Cigar`] equals the returned cost,
+//! - the returned cost is optimal, compared to the reference [`NW`] aligner.
+//!
+//! Failing cases are shrunk to a minimal example before being reported.
+//!
+//! Requires the `testing` feature.
+use crate::*;
+
+/// A (shrunk) failing input for an [`Aligner`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    pub text: Sequence,
+    pub pattern: Sequence,
+    /// Description of the failed check.
+    pub reason: String,
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}\ntext    {}\npattern {}",
+            self.reason,
+            seq_to_string(&self.text),
+            seq_to_string(&self.pattern)
+        )
+    }
+}
+
+/// Run all checks for `aligner` on a single pair.
+///
+/// Returns a description of the first failed check, if any.
+pub fn check_pair(
+    aligner: &mut impl Aligner,
+    cm: &CostModel,
+    text: Seq,
+    pattern: Seq,
+) -> Option<String> {
+    let (cost, cigar) = aligner.align(text, pattern);
+    if let Some(cigar) = cigar {
+        match cigar.verify(cm, text, pattern) {
+            Err(e) => return Some(format!("Invalid cigar {cigar}: {e}")),
+            Ok(cigar_cost) if cigar_cost != cost => {
+                return Some(format!(
+                    "Returned cost {cost} differs from cost {cigar_cost} of cigar {cigar}"
+                ))
+            }
+            Ok(_) => {}
+        }
+    }
+    let (optimal, _) = NW::new(*cm, false).align(text, pattern);
+    if cost != optimal {
+        return Some(format!(
+            "Returned cost {cost} is not optimal cost {optimal}"
+        ));
+    }
+    None
+}
+
+/// Greedily shrink a failing `(text, pattern)` pair.
+///
+/// Repeatedly removes chunks of characters from either sequence as long as `fails` keeps
+/// returning a reason, halving the chunk size until single characters can not be removed.
+pub fn shrink(
+    text: Seq,
+    pattern: Seq,
+    reason: String,
+    mut fails: impl FnMut(Seq, Seq) -> Option<String>,
+) -> Failure {
+    let mut f = Failure {
+        text: text.to_vec(),
+        pattern: pattern.to_vec(),
+        reason,
+    };
+    let mut chunk = f.text.len().max(f.pattern.len()).max(1);
+    loop {
+        let mut progress = false;
+        for side in 0..2 {
+            let mut start = 0;
+            loop {
+                let seq = if side == 0 { &f.text } else { &f.pattern };
+                if start >= seq.len() {
+                    break;
+                }
+                let mut smaller = seq.clone();
+                smaller.drain(start..(start + chunk).min(seq.len()));
+                let (t, p) = if side == 0 {
+                    (&smaller, &f.pattern)
+                } else {
+                    (&f.text, &smaller)
+                };
+                if let Some(reason) = fails(t, p) {
+                    if side == 0 {
+                        f.text = smaller;
+                    } else {
+                        f.pattern = smaller;
+                    }
+                    f.reason = reason;
+                    progress = true;
+                } else {
+                    start += chunk;
+                }
+            }
+        }
+        if !progress {
+            if chunk == 1 {
+                return f;
+            }
+            chunk /= 2;
+        }
+    }
+}
+
+/// Edge cases every aligner must handle.
+pub fn edge_cases() -> Vec<(Sequence, Sequence)> {
+    [
+        (&b""[..], &b""[..]),
+        (b"A", b""),
+        (b"", b"A"),
+        (b"ACGT", b""),
+        (b"", b"ACGT"),
+        (b"A", b"A"),
+        (b"A", b"C"),
+        (b"ACGTACGT", b"ACGTACGT"),
+        (b"AAAAAAAA", b"CCCCCCCC"),
+        (b"AAAAAAAA", b"CC"),
+        (b"CC", b"AAAAAAAA"),
+        (b"AAAAAAAAAAAAAAAA", b"AAAAAAAA"),
+        (b"ACACACACACAC", b"CACACACACACA"),
+    ]
+    .into_iter()
+    .map(|(t, p)| (t.to_vec(), p.to_vec()))
+    .collect()
+}
+
+/// Random sequence pairs of various lengths, error rates, and error models.
+pub fn random_cases() -> Vec<(Sequence, Sequence)> {
+    let mut cases = vec![];
+    let mut seed = 0;
+    for length in [1, 10, 50, 200] {
+        for error_rate in [0.01, 0.1, 0.3, 1.0] {
+            for error_model in [
+                ErrorModel::Uniform,
+                ErrorModel::Repeat { unit_len: 4 },
+                ErrorModel::LongIndel { max_len: 10 },
+            ] {
+                let opts = GenerateOptions {
+                    error_model,
+                    ..GenerateOptions::uniform(length, error_rate)
+                };
+                let (text, pattern, _) = generate_pair(&opts, seed);
+                cases.push((text, pattern));
+                seed += 1;
+            }
+        }
+    }
+    cases
+}
+
+/// Run the full battery of checks on aligners created by `factory`.
+///
+/// A new aligner is constructed for each test case.
+/// Returns the first failure, shrunk to a minimal example.
+pub fn test_aligner<A: Aligner>(
+    cm: &CostModel,
+    mut factory: impl FnMut() -> A,
+) -> Result<(), Failure> {
+    for (text, pattern) in edge_cases().into_iter().chain(random_cases()) {
+        if let Some(reason) = check_pair(&mut factory(), cm, &text, &pattern) {
+            return Err(shrink(&text, &pattern, reason, |t, p| {
+                check_pair(&mut factory(), cm, t, p)
+            }));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nw_passes() {
+        for cm in [
+            CostModel::unit(),
+            CostModel::linear(2, 3),
+            CostModel::affine(1, 2, 1),
+        ] {
+            test_aligner(&cm, || NW::new(cm, true)).unwrap();
+        }
+    }
+
+    /// Always aligns without indels (when lengths are equal).
+    #[derive(Debug)]
+    struct SubsOnly;
+
+    impl Aligner for SubsOnly {
+        fn align(&mut self, a: Seq, b: Seq) -> (Cost, Option<Cigar>) {
+            let cigar = Cigar::resolve_matches(
+                std::iter::once(CigarElem::new(CigarOp::Match, a.len().min(b.len()) as I)),
+                a,
+                b,
+            );
+            let cost = cigar.verify(&CostModel::unit(), a, b).unwrap_or(0);
+            (cost, Some(cigar))
+        }
+    }
+
+    #[test]
+    fn shrinks_failure() {
+        let f = test_aligner(&CostModel::unit(), || SubsOnly).unwrap_err();
+        // Either a length mismatch or a suboptimal alignment of length 2.
+        assert!(f.text.len() + f.pattern.len() <= 2, "{f}");
+    }
+}
@@ -0,0 +1,35 @@
+//! Runs in its own test binary, so that [`CountingAlloc`] only counts the allocations of this test.
+use pa_types::bench::*;
+use pa_types::*;
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+#[test]
+fn bench_nw() {
+    let cm = CostModel::unit();
+    let pairs = generate_dataset(&GenerateOptions::uniform(100, 0.1), 10);
+    let result = bench(&mut NW::new(cm, true), &cm, &pairs, true);
+    assert_eq!(result.pairs, 10);
+    assert_eq!(result.invalid, 0);
+    assert_eq!(result.suboptimal, Some(0));
+    // NW allocates three DP tables of `(n+1) x (m+1)` costs.
+    let tables = pairs
+        .iter()
+        .map(|(text, pattern)| 3 * (text.len() + 1) * (pattern.len() + 1))
+        .max()
+        .unwrap()
+        * std::mem::size_of::<Cost>();
+    assert!(result.peak_memory.unwrap() >= tables);
+
+    // Floats do not necessarily round-trip exactly through JSON.
+    let parsed = serde_json::from_str::<BenchResult>(&result.to_json()).unwrap();
+    assert!((parsed.time_secs - result.time_secs).abs() < 1e-9);
+    assert_eq!(
+        BenchResult {
+            time_secs: result.time_secs,
+            ..parsed
+        },
+        result
+    );
+}